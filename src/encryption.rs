@@ -0,0 +1,96 @@
+use crate::error::{Result, WalletError};
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use bitcoin::secp256k1::rand::{self, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// Marks a file written by `encrypt`, so it isn't mistaken for JSON.
+const MAGIC: &[u8; 8] = b"BMWENC01";
+/// PBKDF2-HMAC-SHA256 rounds for new files. Stored in the header, so it can
+/// be raised without breaking old files.
+const PBKDF2_ROUNDS: u32 = 600_000;
+/// Highest round count `decrypt` accepts. The header isn't authenticated, so
+/// a tampered count must not be able to stall key derivation.
+const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_LEN;
+
+/// Encrypts `plaintext` with AES-256-GCM under a key derived from
+/// `passphrase`. The output is a header (magic, PBKDF2 rounds, salt, nonce)
+/// followed by the ciphertext.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = cipher_for(passphrase, &salt, PBKDF2_ROUNDS);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| WalletError::Encryption("AES-GCM encryption failed".to_string()))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&PBKDF2_ROUNDS.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypts the output of `encrypt`. A wrong passphrase and a tampered
+/// file both fail authentication and are reported the same way.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        return Err(WalletError::Encryption("Not an encrypted wallet file".to_string()));
+    }
+    let (rounds, rest) = data[MAGIC.len()..].split_at(4);
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let rounds = u32::from_be_bytes(rounds.try_into().expect("4 byte slice"));
+    if !(PBKDF2_ROUNDS..=MAX_PBKDF2_ROUNDS).contains(&rounds) {
+        return Err(WalletError::Encryption(format!("Unsupported PBKDF2 round count {}", rounds)));
+    }
+
+    cipher_for(passphrase, salt, rounds)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| WalletError::Encryption("Wrong passphrase or corrupted file".to_string()))
+}
+
+/// Whether `data` starts with the header written by `encrypt`.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8], rounds: u32) -> Aes256Gcm {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut *key);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&*key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let encrypted = encrypt(b"secret descriptor", "correct horse").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(6).any(|window| window == b"secret"));
+        assert_eq!(decrypt(&encrypted, "correct horse").unwrap(), b"secret descriptor");
+
+        assert!(matches!(decrypt(&encrypted, "battery staple"), Err(WalletError::Encryption(_))));
+        assert!(matches!(decrypt(b"{\"version\": 1}", "correct horse"), Err(WalletError::Encryption(_))));
+
+        // Round counts outside the accepted range fail before deriving a key
+        for rounds in [u32::MAX, 1] {
+            let mut tampered = encrypted.clone();
+            tampered[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&rounds.to_be_bytes());
+            assert!(matches!(decrypt(&tampered, "correct horse"), Err(WalletError::Encryption(_))));
+        }
+    }
+}
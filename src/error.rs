@@ -0,0 +1,227 @@
+use bdk_esplora::esplora_client;
+use bdk_wallet::{
+    chain::local_chain::CannotConnectError, coin_selection::InsufficientFunds, descriptor::DescriptorError,
+    error::CreateTxError, miniscript, LoadError,
+};
+use bitcoin::{address::ParseError, bip32, Amount, Network, Txid};
+use std::{fmt, path::PathBuf};
+
+pub type Result<T, E = WalletError> = std::result::Result<T, E>;
+
+/// Errors returned by the wallet library.
+#[derive(Debug)]
+pub enum WalletError {
+    /// The multisig threshold is larger than the number of keys
+    ThresholdTooHigh { threshold: usize, keys: usize },
+    /// An xpub or descriptor key could not be parsed
+    InvalidXpub(String),
+    /// An address or key belongs to a different network
+    NetworkMismatch { expected: Network },
+    /// The address does not belong to this wallet
+    NotMine(String),
+    /// The descriptor has no address at this index
+    AddressIndexOutOfRange { index: u32, count: u32 },
+    /// The network name is unknown or not supported for this operation
+    UnsupportedNetwork(String),
+    /// An address string could not be parsed
+    Address(ParseError),
+    /// A descriptor could not be parsed or used
+    Descriptor(String),
+    /// A key could not be derived or parsed
+    Key(String),
+    /// No tweak candidate produced valid beacon keys
+    InvalidTweak,
+    /// The OP_RETURN payload exceeds the standard size
+    OpReturnTooLarge { size: usize, max: usize },
+    /// The wallet file was written by a newer version
+    UnsupportedVersion(u32),
+    /// The home directory could not be determined
+    HomeDirNotFound,
+    /// The wallet has no file path to save to
+    NoWalletPath,
+    /// A different wallet is already saved at the path
+    WalletConflict { path: PathBuf, existing: String },
+    /// Wallet names may only contain letters, digits, '-' and '_'
+    InvalidWalletName(String),
+    /// A chain update could not be applied to the wallet
+    Sync(String),
+    /// A transaction could not be built
+    Transaction(String),
+    /// An amount string could not be parsed
+    InvalidAmount(String),
+    /// The selected coins can't pay for the outputs and fee
+    InsufficientFunds { needed: Amount, available: Amount },
+    /// The requested fee is outside acceptable bounds
+    InvalidFee(String),
+    /// An output is below the dust limit for its script type
+    BelowDust { amount: Amount, limit: Amount },
+    /// An xpub is not at the account-level depth expected for multisig
+    UnexpectedKeyDepth { xpub: String, depth: u8 },
+    /// The backend does not know the transaction
+    TxNotFound(Txid),
+    /// The cached wallet state does not match the wallet
+    Cache(String),
+    /// A file could not be encrypted or decrypted
+    Encryption(String),
+    /// The operation was cancelled by the caller
+    Cancelled,
+    /// The Esplora backend returned an error
+    Esplora(Box<esplora_client::Error>),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::ThresholdTooHigh { threshold, keys } => {
+                write!(f, "Threshold {} cannot exceed number of keys ({})", threshold, keys)
+            }
+            WalletError::InvalidXpub(e) => write!(f, "Invalid xpub: {}", e),
+            WalletError::NetworkMismatch { expected } => write!(f, "Expected network {}", expected),
+            WalletError::NotMine(address) => write!(f, "Address {} does not belong to this wallet", address),
+            WalletError::AddressIndexOutOfRange { index, count } => {
+                write!(f, "Address index {} is out of range; the wallet has {} addresses", index, count)
+            }
+            WalletError::UnsupportedNetwork(network) => write!(f, "Unsupported network: {}", network),
+            WalletError::Address(e) => write!(f, "Invalid address: {}", e),
+            WalletError::Descriptor(e) => write!(f, "Invalid descriptor: {}", e),
+            WalletError::Key(e) => write!(f, "Key error: {}", e),
+            WalletError::InvalidTweak => write!(f, "Could not find a valid beacon tweak"),
+            WalletError::OpReturnTooLarge { size, max } => {
+                write!(f, "OP_RETURN payload is {} bytes, over the {}-byte standard relay limit", size, max)
+            }
+            WalletError::UnsupportedVersion(version) => write!(f, "Unsupported wallet file version {}", version),
+            WalletError::HomeDirNotFound => write!(f, "Could not find home directory"),
+            WalletError::NoWalletPath => write!(f, "Wallet has no file path; call set_path first"),
+            WalletError::WalletConflict { path, existing } => write!(
+                f,
+                "{} already holds a different wallet ({}); refusing to overwrite it",
+                path.display(),
+                existing
+            ),
+            WalletError::InvalidWalletName(name) => write!(f, "Invalid wallet name: '{}'", name),
+            WalletError::Sync(e) => write!(f, "Failed to apply chain update: {}", e),
+            WalletError::Transaction(e) => write!(f, "Failed to build transaction: {}", e),
+            WalletError::InvalidAmount(e) => write!(f, "Invalid amount: {}", e),
+            WalletError::InsufficientFunds { needed, available } => write!(
+                f,
+                "Insufficient funds: need {}, have {}, short by {}",
+                needed,
+                available,
+                *needed - *available
+            ),
+            WalletError::InvalidFee(e) => write!(f, "Invalid fee: {}", e),
+            WalletError::BelowDust { amount, limit } => {
+                write!(f, "Output of {} is below the dust limit of {}", amount, limit)
+            }
+            WalletError::UnexpectedKeyDepth { xpub, depth } => write!(
+                f,
+                "Xpub {} is at depth {}, expected an account-level key at depth 4",
+                xpub, depth
+            ),
+            WalletError::TxNotFound(txid) => write!(f, "Transaction {} not found", txid),
+            WalletError::Cache(e) => write!(f, "Failed to load cached wallet state: {}", e),
+            WalletError::Encryption(e) => write!(f, "Encryption error: {}", e),
+            WalletError::Cancelled => write!(f, "Operation cancelled"),
+            WalletError::Esplora(e) => write!(f, "Esplora error: {}", e),
+            WalletError::Io(e) => write!(f, "I/O error: {}", e),
+            WalletError::Json(e) => write!(f, "JSON error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalletError::Address(e) => Some(e),
+            WalletError::Esplora(e) => Some(e.as_ref()),
+            WalletError::Io(e) => Some(e),
+            WalletError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WalletError {
+    fn from(e: std::io::Error) -> Self {
+        WalletError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for WalletError {
+    fn from(e: serde_json::Error) -> Self {
+        WalletError::Json(e)
+    }
+}
+
+impl From<ParseError> for WalletError {
+    fn from(e: ParseError) -> Self {
+        WalletError::Address(e)
+    }
+}
+
+impl From<bip32::Error> for WalletError {
+    fn from(e: bip32::Error) -> Self {
+        WalletError::Key(e.to_string())
+    }
+}
+
+impl From<bitcoin::secp256k1::Error> for WalletError {
+    fn from(e: bitcoin::secp256k1::Error) -> Self {
+        WalletError::Key(e.to_string())
+    }
+}
+
+impl From<secp256k1::Error> for WalletError {
+    fn from(e: secp256k1::Error) -> Self {
+        WalletError::Key(e.to_string())
+    }
+}
+
+impl From<miniscript::Error> for WalletError {
+    fn from(e: miniscript::Error) -> Self {
+        WalletError::Descriptor(e.to_string())
+    }
+}
+
+impl From<DescriptorError> for WalletError {
+    fn from(e: DescriptorError) -> Self {
+        WalletError::Descriptor(e.to_string())
+    }
+}
+
+impl From<CannotConnectError> for WalletError {
+    fn from(e: CannotConnectError) -> Self {
+        WalletError::Sync(e.to_string())
+    }
+}
+
+impl From<CreateTxError> for WalletError {
+    fn from(e: CreateTxError) -> Self {
+        match e {
+            CreateTxError::CoinSelection(InsufficientFunds { needed, available }) => {
+                WalletError::InsufficientFunds { needed, available }
+            }
+            e => WalletError::Transaction(e.to_string()),
+        }
+    }
+}
+
+impl From<LoadError> for WalletError {
+    fn from(e: LoadError) -> Self {
+        WalletError::Cache(e.to_string())
+    }
+}
+
+impl From<Box<esplora_client::Error>> for WalletError {
+    fn from(e: Box<esplora_client::Error>) -> Self {
+        WalletError::Esplora(e)
+    }
+}
+
+impl From<esplora_client::Error> for WalletError {
+    fn from(e: esplora_client::Error) -> Self {
+        WalletError::Esplora(Box::new(e))
+    }
+}
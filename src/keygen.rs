@@ -1,32 +1,122 @@
-use anyhow::Result;
+use crate::encryption;
+use crate::error::{Result, WalletError};
+use crate::utilities::coin_type;
+use crate::wallet::ScriptType;
 use bitcoin::{
     Network,
-    secp256k1::{Secp256k1, rand::{self, RngCore}},
-    bip32::{ExtendedPrivKey, ExtendedPubKey, DerivationPath},
+    secp256k1::{All, Secp256k1, rand::{self, RngCore}},
+    bip32::{Xpriv, Xpub, DerivationPath},
 };
+use bip39::{Language, Mnemonic};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::fmt;
 use std::fs;
 use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct KeyPair {
     pub xpub: String,
+    /// Wiped when the key is dropped
     #[serde(skip)]
-    pub xpriv: Option<String>,
+    pub xpriv: Option<Zeroizing<String>>,
     pub fingerprint: String,
     pub network: Network,
+    /// Derivation path of the xpub, e.g. m/84'/1'/0'
+    #[serde(default)]
+    pub derivation_path: String,
+}
+
+/// Plaintext of an encrypted key file: the key file fields plus the xpriv,
+/// borrowed so serializing doesn't leave a copy of it behind.
+#[derive(Serialize)]
+struct EncryptedKeyRef<'a> {
+    #[serde(flatten)]
+    key: &'a KeyPair,
+    xpriv: Option<&'a str>,
+}
+
+/// The xpriv field of an encrypted key file's plaintext.
+#[derive(Deserialize)]
+struct EncryptedXpriv {
+    xpriv: Option<String>,
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("xpub", &self.xpub)
+            .field("xpriv", &self.xpriv.as_ref().map(|_| "<redacted>"))
+            .field("fingerprint", &self.fingerprint)
+            .field("network", &self.network)
+            .field("derivation_path", &self.derivation_path)
+            .finish()
+    }
 }
 
+impl KeyPair {
+    /// Wipes the xpriv now rather than whenever the key is dropped. `KeyPair`
+    /// has no mnemonic field; the seed never outlives `KeyGenerator`'s
+    /// derivation.
+    pub fn zeroize(mut self) {
+        if let Some(xpriv) = self.xpriv.as_mut() {
+            xpriv.zeroize();
+        }
+    }
+
+    /// Writes the key to `path` encrypted with `passphrase`. Unlike the
+    /// plaintext key files, this keeps the xpriv. Give the file an extension
+    /// other than `.json` (e.g. `key_0.json.enc`) so `list_keys` skips it.
+    pub fn save_encrypted(&self, path: &Path, passphrase: &str) -> Result<()> {
+        let file = EncryptedKeyRef { key: self, xpriv: self.xpriv.as_deref().map(String::as_str) };
+        let plaintext = Zeroizing::new(serde_json::to_vec(&file)?);
+        fs::write(path, encryption::encrypt(&plaintext, passphrase)?)?;
+        Ok(())
+    }
+
+    /// Loads a key written by `save_encrypted`, xpriv included. A wrong
+    /// passphrase fails with `WalletError::Encryption`.
+    pub fn load_encrypted(path: &Path, passphrase: &str) -> Result<Self> {
+        let plaintext = Zeroizing::new(encryption::decrypt(&fs::read(path)?, passphrase)?);
+        let mut keypair: KeyPair = serde_json::from_slice(&plaintext)?;
+        let EncryptedXpriv { xpriv } = serde_json::from_slice(&plaintext)?;
+        keypair.xpriv = xpriv.map(Zeroizing::new);
+        Ok(keypair)
+    }
+}
+
+/// Shareable view of a key: everything a cosigner needs, no secrets.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyEntry {
+    pub xpub: String,
+    pub fingerprint: String,
+    pub derivation_path: String,
+}
+
+/// Extended private key whose secret key is wiped on drop, for the master
+/// and derived keys that only live during a derivation.
+struct SecretXpriv(Xpriv);
+
+impl Drop for SecretXpriv {
+    fn drop(&mut self) {
+        self.0.private_key.non_secure_erase();
+    }
+}
+
+impl ZeroizeOnDrop for SecretXpriv {}
+
 pub struct KeyGenerator {
     network: Network,
     storage_path: PathBuf,
+    /// Context shared by every derivation
+    secp: Secp256k1<All>,
 }
 
 impl KeyGenerator {
     pub fn new(network: Network) -> Result<Self> {
         let key_dir = dirs::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
+            .ok_or(WalletError::HomeDirNotFound)?
             .join(".bitcoin-multisig")
             .join("keys");
         
@@ -35,43 +125,128 @@ impl KeyGenerator {
         Ok(KeyGenerator {
             network,
             storage_path: key_dir,
+            secp: Secp256k1::new(),
         })
     }
 
     pub fn generate_key(&self, index: u32) -> Result<KeyPair> {
-        let secp = Secp256k1::new();
-        
-        // Generate random seed
-        let mut seed = [0u8; 32];
-        rand::thread_rng().fill_bytes(&mut seed);
-        
-        // Generate master private key
-        let xpriv = ExtendedPrivKey::new_master(self.network, &seed)?;
-        
-        // Derive using BIP84 path (m/84'/0'/0')
-        let path = match self.network {
-            Network::Bitcoin => "m/84'/0'/0'",
-            Network::Testnet => "m/84'/1'/0'",
-            Network::Regtest => "m/84'/1'/0'",
-            _ => return Err(anyhow::anyhow!("Unsupported network")),
+        self.generate_key_from_seed(&Self::random_seed(), index)
+    }
+
+    /// Generates a key at the index after the highest one already stored,
+    /// so earlier `key_<index>.json` files are never overwritten. Returns the
+    /// key and its index.
+    pub fn generate_next_key(&self) -> Result<(KeyPair, u32)> {
+        let index = self.next_free_index()?;
+        Ok((self.generate_key(index)?, index))
+    }
+
+    fn next_free_index(&self) -> Result<u32> {
+        let mut highest = None;
+        for entry in fs::read_dir(&self.storage_path)? {
+            let file_name = entry?.file_name();
+            let Some(rest) = file_name.to_str().and_then(|name| name.strip_prefix("key_")) else {
+                continue;
+            };
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(index) = digits.parse::<u32>() {
+                highest = highest.max(Some(index));
+            }
+        }
+        match highest {
+            None => Ok(0),
+            Some(index) => index
+                .checked_add(1)
+                .ok_or_else(|| WalletError::Key("No free key index left".to_string())),
+        }
+    }
+
+    /// Generates a key derived at BIP84 account `account` (m/84'/coin'/account').
+    pub fn generate_key_for_account(&self, index: u32, account: u32) -> Result<KeyPair> {
+        self.derive_key(&Self::random_seed(), index, account)
+    }
+
+    /// Derives a key from caller-provided entropy instead of the RNG, so the
+    /// same seed always yields the same key.
+    pub fn generate_key_from_seed(&self, seed: &[u8; 32], index: u32) -> Result<KeyPair> {
+        self.derive_key(seed, index, 0)
+    }
+
+    /// Derives a key from a 24-word mnemonic in `language`, using its
+    /// entropy as the seed like `generate_key_from_seed`.
+    pub fn generate_key_from_mnemonic(&self, phrase: &str, language: Language, index: u32) -> Result<KeyPair> {
+        let mnemonic = Mnemonic::parse_in(language, phrase)
+            .map_err(|e| WalletError::Key(format!("Invalid {:?} mnemonic: {}", language, e)))?;
+        let entropy = Zeroizing::new(mnemonic.to_entropy());
+        let seed: Zeroizing<[u8; 32]> = Zeroizing::new(
+            entropy
+                .as_slice()
+                .try_into()
+                .map_err(|_| WalletError::Key("Mnemonic must have 24 words".to_string()))?,
+        );
+        self.generate_key_from_seed(&seed, index)
+    }
+
+    /// Fresh seed, wiped once the caller is done with it.
+    fn random_seed() -> Zeroizing<[u8; 32]> {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill_bytes(&mut *seed);
+        seed
+    }
+
+    /// Generates a multisig key on the BIP48 path
+    /// m/48'/coin'/account'/script_type', as hardware wallets export:
+    /// `1'` for P2SH-P2WSH and `2'` for P2WSH.
+    pub fn generate_bip48(&self, index: u32, account: u32, script_type: ScriptType) -> Result<KeyPair> {
+        Self::check_account(account)?;
+        let branch = match script_type {
+            ScriptType::P2shP2wsh => 1,
+            ScriptType::P2wsh => 2,
+            ScriptType::P2tr => {
+                return Err(WalletError::Key("BIP48 does not define a taproot script type".to_string()))
+            }
         };
+        let path = format!("m/48'/{}'/{}'/{}'", coin_type(self.network)?, account, branch);
+        let file_name = format!("key_{}_bip48_{}_{}.json", index, account, branch);
+        self.derive_at_path(&Self::random_seed(), path, &file_name)
+    }
+
+    fn check_account(account: u32) -> Result<()> {
+        if account >= 1 << 31 {
+            return Err(WalletError::Key(format!("Account {} is out of range", account)));
+        }
+        Ok(())
+    }
+
+    fn derive_key(&self, seed: &[u8; 32], index: u32, account: u32) -> Result<KeyPair> {
+        Self::check_account(account)?;
+
+        // Derive using BIP84 path (m/84'/0'/account')
+        let path = format!("m/84'/{}'/{}'", coin_type(self.network)?, account);
+        self.derive_at_path(seed, path, &Self::key_file_name(index, account))
+    }
+
+    fn derive_at_path(&self, seed: &[u8; 32], path: String, file_name: &str) -> Result<KeyPair> {
+        // Generate master private key
+        let xpriv = SecretXpriv(Xpriv::new_master(self.network, seed)?);
         
-        let derivation_path = DerivationPath::from_str(path)?;
-        let derived_xpriv = xpriv.derive_priv(&secp, &derivation_path)?;
+        let derivation_path = DerivationPath::from_str(&path)?;
+        let derived_xpriv = SecretXpriv(xpriv.0.derive_priv(&self.secp, &derivation_path)?);
         
         // Get xpub and fingerprint
-        let xpub = ExtendedPubKey::from_priv(&secp, &derived_xpriv);
-        let fingerprint = derived_xpriv.fingerprint(&secp).to_string();
+        let xpub = Xpub::from_priv(&self.secp, &derived_xpriv.0);
+        let fingerprint = derived_xpriv.0.fingerprint(&self.secp).to_string();
         
         let keypair = KeyPair {
             xpub: xpub.to_string(),
-            xpriv: Some(derived_xpriv.to_string()),
+            xpriv: Some(Zeroizing::new(derived_xpriv.0.to_string())),
             fingerprint,
             network: self.network,
+            derivation_path: path,
         };
         
         // Save to file
-        self.save_keypair(&keypair, index)?;
+        self.save_keypair(&keypair, file_name)?;
         
         Ok(keypair)
     }
@@ -89,10 +264,249 @@ impl KeyGenerator {
         Ok(keys)
     }
 
-    fn save_keypair(&self, keypair: &KeyPair, index: u32) -> Result<()> {
-        let file_path = self.storage_path.join(format!("key_{}.json", index));
+    /// Stored keys on `network` and/or with the given fingerprint (hex, any
+    /// case). `None` matches everything, so this can locate the key to sign
+    /// with from a PSBT's fingerprint.
+    pub fn list_keys_filtered(&self, network: Option<Network>, fingerprint: Option<&str>) -> Result<Vec<KeyPair>> {
+        Ok(self
+            .list_keys()?
+            .into_iter()
+            .filter(|key| network.is_none_or(|network| key.network == network))
+            .filter(|key| fingerprint.is_none_or(|fp| key.fingerprint.eq_ignore_ascii_case(fp)))
+            .collect())
+    }
+
+    /// Writes the stored keys' xpubs, fingerprints and derivation paths to
+    /// `path` as a JSON array. Safe to hand to cosigners.
+    pub fn export_public_manifest(&self, path: &Path) -> Result<()> {
+        let mut manifest: Vec<PublicKeyEntry> = self
+            .list_keys()?
+            .into_iter()
+            .map(|key| PublicKeyEntry {
+                xpub: key.xpub,
+                fingerprint: key.fingerprint,
+                derivation_path: key.derivation_path,
+            })
+            .collect();
+        manifest.sort_by(|a, b| a.fingerprint.cmp(&b.fingerprint));
+        fs::write(path, serde_json::to_string_pretty(&manifest)?)?;
+        Ok(())
+    }
+
+    fn key_file_name(index: u32, account: u32) -> String {
+        // Account 0 keeps the original file name
+        if account == 0 {
+            format!("key_{}.json", index)
+        } else {
+            format!("key_{}_account_{}.json", index, account)
+        }
+    }
+
+    /// Writes the plaintext key file. The xpriv is left out, but the file
+    /// still links the xpub to this machine; see `KeyPair::save_encrypted`.
+    fn save_keypair(&self, keypair: &KeyPair, file_name: &str) -> Result<()> {
+        let file_path = self.storage_path.join(file_name);
         let json = serde_json::to_string_pretty(keypair)?;
         fs::write(file_path, json)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_key_for_account() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-account-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let account0 = keygen.generate_key_for_account(900, 0).unwrap();
+        let account1 = keygen.generate_key_for_account(900, 1).unwrap();
+
+        assert_ne!(account0.xpub, account1.xpub);
+        assert_eq!(account0.derivation_path, "m/84'/1'/0'");
+        assert_eq!(account1.derivation_path, "m/84'/1'/1'");
+
+        let stored0 = keygen.storage_path.join("key_900.json");
+        let stored1 = keygen.storage_path.join("key_900_account_1.json");
+        let loaded0: KeyPair = serde_json::from_str(&fs::read_to_string(&stored0).unwrap()).unwrap();
+        let loaded1: KeyPair = serde_json::from_str(&fs::read_to_string(&stored1).unwrap()).unwrap();
+        assert_eq!(loaded0.derivation_path, account0.derivation_path);
+        assert_eq!(loaded1.derivation_path, account1.derivation_path);
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_many_keys_shared_context() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-many-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let mut xpubs = std::collections::HashSet::new();
+        for index in 1000..1100 {
+            let key = keygen.generate_key(index).unwrap();
+            assert_eq!(key.derivation_path, "m/84'/1'/0'");
+            assert_eq!(key.fingerprint.len(), 8);
+            assert!(xpubs.insert(key.xpub));
+        }
+        assert_eq!(xpubs.len(), 100);
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_key_from_seed_is_deterministic() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-seed-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let seed = [7u8; 32];
+        let first = keygen.generate_key_from_seed(&seed, 901).unwrap();
+        let second = keygen.generate_key_from_seed(&seed, 901).unwrap();
+
+        assert_eq!(first.xpub, second.xpub);
+        assert_eq!(first.fingerprint, second.fingerprint);
+        assert_ne!(keygen.generate_key_from_seed(&[8u8; 32], 901).unwrap().xpub, first.xpub);
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_export_public_manifest_has_no_secrets() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-manifest-keys");
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let key = keygen.generate_key_from_seed(&[9u8; 32], 0).unwrap();
+        keygen.generate_key_from_seed(&[10u8; 32], 1).unwrap();
+
+        let manifest_path = std::env::temp_dir().join("bitcoin-multisig-manifest.json");
+        keygen.export_public_manifest(&manifest_path).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+
+        let entries = manifest.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        for entry in entries {
+            let entry = entry.as_object().unwrap();
+            assert!(entry.contains_key("xpub"));
+            assert!(entry.contains_key("fingerprint"));
+            assert!(!entry.contains_key("mnemonic"));
+            assert!(!entry.contains_key("xpriv"));
+        }
+        assert!(entries.iter().any(|entry| entry["xpub"] == key.xpub.as_str()));
+
+        fs::remove_file(manifest_path).unwrap();
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_bip48_script_type_branch() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-bip48-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let p2wsh = keygen.generate_bip48(902, 0, ScriptType::P2wsh).unwrap();
+        let nested = keygen.generate_bip48(902, 0, ScriptType::P2shP2wsh).unwrap();
+
+        assert_eq!(p2wsh.derivation_path, "m/48'/1'/0'/2'");
+        assert!(p2wsh.derivation_path.ends_with("2'"));
+        assert_eq!(nested.derivation_path, "m/48'/1'/0'/1'");
+        assert!(nested.derivation_path.ends_with("1'"));
+        assert!(keygen.generate_bip48(902, 0, ScriptType::P2tr).is_err());
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_key_from_mnemonic() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-mnemonic-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let seed = [11u8; 32];
+        let phrase = Mnemonic::from_entropy_in(Language::Japanese, &seed).unwrap().to_string();
+
+        let from_phrase = keygen.generate_key_from_mnemonic(&phrase, Language::Japanese, 903).unwrap();
+        let from_seed = keygen.generate_key_from_seed(&seed, 903).unwrap();
+        assert_eq!(from_phrase.xpub, from_seed.xpub);
+        assert!(keygen.generate_key_from_mnemonic(&phrase, Language::English, 903).is_err());
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_list_keys_filtered() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-filtered-keys");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let mainnet = KeyGenerator { network: Network::Bitcoin, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let testnet = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let main_key = mainnet.generate_key_from_seed(&[12u8; 32], 904).unwrap();
+        let test_key = testnet.generate_key_from_seed(&[12u8; 32], 905).unwrap();
+
+        let xpubs = |keys: Vec<KeyPair>| keys.into_iter().map(|key| key.xpub).collect::<Vec<_>>();
+        let on_mainnet = xpubs(testnet.list_keys_filtered(Some(Network::Bitcoin), None).unwrap());
+        assert!(on_mainnet.contains(&main_key.xpub));
+        assert!(!on_mainnet.contains(&test_key.xpub));
+        let on_testnet = xpubs(testnet.list_keys_filtered(Some(Network::Testnet), None).unwrap());
+        assert!(on_testnet.contains(&test_key.xpub));
+        assert!(!on_testnet.contains(&main_key.xpub));
+
+        let by_fingerprint = testnet
+            .list_keys_filtered(None, Some(&main_key.fingerprint.to_uppercase()))
+            .unwrap();
+        assert_eq!(xpubs(by_fingerprint), vec![main_key.xpub.clone()]);
+        assert!(testnet
+            .list_keys_filtered(Some(Network::Testnet), Some(&main_key.fingerprint))
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_keypair_encrypted_round_trip() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-encrypted-key");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+        let key = keygen.generate_key_from_seed(&[13u8; 32], 0).unwrap();
+
+        let path = storage_path.join("key_0.json.enc");
+        key.save_encrypted(&path, "open sesame").unwrap();
+        assert!(matches!(KeyPair::load_encrypted(&path, "wrong"), Err(WalletError::Encryption(_))));
+        let loaded = KeyPair::load_encrypted(&path, "open sesame").unwrap();
+        assert_eq!(loaded.xpub, key.xpub);
+        assert_eq!(loaded.xpriv, key.xpriv);
+        assert!(loaded.xpriv.is_some());
+        assert!(format!("{:?}", loaded).contains("<redacted>"));
+        assert!(!format!("{:?}", loaded).contains(loaded.xpriv.as_deref().unwrap().as_str()));
+        loaded.zeroize();
+        // The encrypted copy doesn't show up as a second key
+        assert_eq!(keygen.list_keys().unwrap().len(), 1);
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_next_key_picks_free_index() {
+        let storage_path = std::env::temp_dir().join("bitcoin-multisig-next-key");
+        let _ = fs::remove_dir_all(&storage_path);
+        fs::create_dir_all(&storage_path).unwrap();
+        let keygen = KeyGenerator { network: Network::Testnet, storage_path: storage_path.clone(), secp: Secp256k1::new() };
+
+        let indexes: Vec<u32> = (0..3).map(|_| keygen.generate_next_key().unwrap().1).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+        for index in 0..3 {
+            assert!(storage_path.join(format!("key_{}.json", index)).exists());
+        }
+
+        // Account and BIP48 files count towards the highest index too
+        keygen.generate_bip48(7, 0, ScriptType::P2wsh).unwrap();
+        assert_eq!(keygen.generate_next_key().unwrap().1, 8);
+
+        fs::remove_dir_all(storage_path).unwrap();
+    }
+}
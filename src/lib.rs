@@ -1,9 +1,12 @@
+pub mod encryption;
+pub mod error;
 pub mod keygen;
 pub mod wallet;
 pub mod beacon;
 
 pub mod utilities;
 
+pub use error::WalletError;
 pub use keygen::KeyGenerator;
-pub use wallet::MultisigWallet;
-pub use beacon::{derive_beacon_keys, create_beacon_address};
+pub use wallet::{AddressStatus, Backend, ChangePolicy, FeeLimits, MultisigWallet, ScanProgress, ScriptType, SigningProgress, TxSummary, TxVerification, Utxo, WalletStatus};
+pub use beacon::{derive_beacon_context, derive_beacon_keys, create_beacon_address, create_beacon_address_timelocked, parse_beacon_payload, verify_beacon_address, BeaconContext, BeaconSet, BeaconTimelock, BeaconVector};
@@ -1,27 +1,19 @@
 use anyhow::{Result, anyhow};
-use bitcoin::Address;
-use bitcoin::{Network, bip32::Xpub};
+use bitcoin::{Amount, Network, Psbt, bip32::{Xpriv, Xpub}};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use dotenv::dotenv;
 use std::{env, fs};
-use dirs;
 
-mod keygen;
-mod wallet;
-
-use crate::keygen::KeyGenerator;
-use crate::wallet::MultisigWallet;
+use bitcoin_multisig_wallet::{KeyGenerator, MultisigWallet};
+use bitcoin_multisig_wallet::utilities::{
+    format_btc, get_network_from_string, network_to_string, parse_address, parse_amount, read_keys_file,
+};
 
 fn get_network_from_env() -> Result<Network> {
     let network = env::var("NETWORK").unwrap_or_else(|_| "testnet".to_string());
-    match network.to_lowercase().as_str() {
-        "bitcoin" => Ok(Network::Bitcoin),
-        "testnet" => Ok(Network::Testnet),
-        "regtest" => Ok(Network::Regtest),
-        _ => Err(anyhow!("Invalid network in environment: {}", network)),
-    }
+    Ok(get_network_from_string(&network)?)
 }
 
 fn get_wallet_dir() -> PathBuf {
@@ -46,6 +38,8 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+// DRYRUN_* keep their names as subcommands
+#[allow(non_camel_case_types)]
 enum Commands {
     /// Generate a new key pair
     GenerateKey {
@@ -67,9 +61,15 @@ enum Commands {
         /// Number of required signatures. Defaults to value from .env file
         #[arg(short, long)]
         threshold: Option<usize>,
-        /// List of xpub keys
+        /// List of xpub keys, optionally with a derivation suffix (e.g. xpub.../1/*)
         #[arg(short, long)]
         xpubs: Vec<String>,
+        /// File of xpub keys, one per line or as a JSON array, merged with --xpubs
+        #[arg(long)]
+        xpubs_file: Option<PathBuf>,
+        /// Replace an existing wallet file holding a different wallet
+        #[arg(long)]
+        force: bool,
     },
     /// Get a new address from the wallet
     GetAddress {
@@ -82,6 +82,90 @@ enum Commands {
         /// Path to the wallet file
         #[arg(short, long)]
         wallet: Option<PathBuf>,
+        /// Ignore cached sync state and rescan from scratch
+        #[arg(long)]
+        refresh: bool,
+        /// Scan with a small gap limit; faster, but may miss funds
+        #[arg(long, conflicts_with = "refresh")]
+        quick: bool,
+    },
+    /// Sync once and show a wallet summary
+    Status {
+        /// Path to the wallet file
+        #[arg(short, long)]
+        wallet: Option<PathBuf>,
+        /// Print the summary as a JSON object
+        #[arg(long)]
+        json: bool,
+        /// Ignore cached sync state and rescan from scratch
+        #[arg(long)]
+        refresh: bool,
+        /// Scan with a small gap limit; faster, but may miss funds
+        #[arg(long, conflicts_with = "refresh")]
+        quick: bool,
+    },
+    /// Print the wallet's public descriptor with checksum
+    ExportDescriptor {
+        /// Path to the wallet file
+        #[arg(short, long)]
+        wallet: Option<PathBuf>,
+    },
+    /// Create a watch-only wallet file from a descriptor
+    ImportDescriptor {
+        /// Descriptor to import
+        #[arg(short, long)]
+        descriptor: String,
+        /// Network (bitcoin, testnet, regtest). Defaults to value from .env file
+        #[arg(short, long)]
+        network: Option<String>,
+        /// Path to write the wallet file to
+        #[arg(short, long)]
+        wallet: Option<PathBuf>,
+        /// Replace an existing wallet file holding a different wallet
+        #[arg(long)]
+        force: bool,
+    },
+    /// Sign a binary PSBT file offline and write the result to another file
+    SignFile {
+        /// PSBT file to sign
+        #[arg(long)]
+        psbt_in: PathBuf,
+        /// Where to write the signed PSBT
+        #[arg(long)]
+        psbt_out: PathBuf,
+        /// File of extended private keys, one per line or as a JSON array
+        #[arg(long)]
+        key_file: PathBuf,
+        /// Path to the wallet file
+        #[arg(short, long)]
+        wallet: Option<PathBuf>,
+    },
+    /// Derive the pairwise 2-of-2 beacon addresses for the keys in a keys manifest
+    Beacon {
+        /// Keys manifest with a `publicKey` per cosigner
+        #[arg(short, long, default_value = "keys.json")]
+        keys_file: PathBuf,
+        /// Network (bitcoin, testnet, regtest). Defaults to value from .env file
+        #[arg(short, long)]
+        network: Option<String>,
+        /// Print the beacons as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Build an unsigned PSBT paying an address
+    Send {
+        /// Address to pay
+        #[arg(long)]
+        to: String,
+        /// Amount to send, in BTC (0.001 or 0.001btc) or sats (100000sat)
+        #[arg(long)]
+        amount: String,
+        /// Absolute fee, in BTC or sats
+        #[arg(long)]
+        fee: String,
+        /// Path to the wallet file
+        #[arg(short, long)]
+        wallet: Option<PathBuf>,
     },
     /// List wwallet transactions
     ListTransactions {
@@ -93,52 +177,54 @@ enum Commands {
         /// Network (bitcoin, testnet, regtest). Defaults to value from .env file
         #[arg(short, long)]
         network_str: Option<String>,
+        /// Replace an existing wallet file holding a different wallet
+        #[arg(long)]
+        force: bool,
     },
 
     DRYRUN_2,
     
     /// Run test program
-    Test,
+    Test {
+        /// Replace an existing wallet file holding a different wallet
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 fn main() -> Result<()> {
     // Load environment variables from .env file
     dotenv().ok();
+    // The library reports through `log`; show its info and warnings unless
+    // RUST_LOG says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("bitcoin_multisig_wallet=info"))
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
     
     let cli = Cli::parse();
 
     match cli.command {
         Commands::GenerateKey { network } => {
-            let network = if let Some(net) = network {
-                match net.as_str() {
-                    "bitcoin" => Network::Bitcoin,
-                    "testnet" => Network::Testnet,
-                    "regtest" => Network::Regtest,
-                    _ => return Err(anyhow!("Invalid network")),
-                }
-            } else {
-                get_network_from_env()?
+            let network = match network {
+                Some(net) => get_network_from_string(&net)?,
+                None => get_network_from_env()?,
             };
 
             let keygen = KeyGenerator::new(network)?;
-            let key = keygen.generate_key(0)?;
-            println!("Generated key:");
+            let (key, index) = keygen.generate_next_key()?;
+            println!("Generated key {}:", index);
             println!("  XPub: {}", key.xpub);
-            if let Some(xpriv) = key.xpriv {
-                println!("  XPriv: {}", xpriv);
+            if let Some(xpriv) = &key.xpriv {
+                println!("  XPriv: {}", xpriv.as_str());
             }
             println!("  Fingerprint: {}", key.fingerprint);
         }
         Commands::ListKeys { network } => {
-            let network = if let Some(net) = network {
-                match net.as_str() {
-                    "bitcoin" => Network::Bitcoin,
-                    "testnet" => Network::Testnet,
-                    "regtest" => Network::Regtest,
-                    _ => return Err(anyhow!("Invalid network")),
-                }
-            } else {
-                get_network_from_env()?
+            // Only validated; keys.json isn't split by network
+            let _network = match network {
+                Some(net) => get_network_from_string(&net)?,
+                None => get_network_from_env()?,
             };
 
             use std::fs;
@@ -172,48 +258,144 @@ fn main() -> Result<()> {
                 println!("  Mnemonic: {}", mnemonic);
             }
         }
-        Commands::CreateWallet { network, threshold, xpubs } => {
-            let network = if let Some(net) = network {
-                match net.as_str() {
-                    "bitcoin" => Network::Bitcoin,
-                    "testnet" => Network::Testnet,
-                    "regtest" => Network::Regtest,
-                    _ => return Err(anyhow!("Invalid network")),
-                }
-            } else {
-                get_network_from_env()?
+        Commands::CreateWallet { network, threshold, mut xpubs, xpubs_file, force } => {
+            let network = match network {
+                Some(net) => get_network_from_string(&net)?,
+                None => get_network_from_env()?,
             };
 
             let threshold = threshold.unwrap_or_else(get_default_threshold);
-            
-            let xpub_keys: Result<Vec<Xpub>> = xpubs
-                .iter()
-                .map(|x| Xpub::from_str(x).map_err(|e| anyhow!("Invalid xpub: {}", e)))
-                .collect();
 
-            let wallet = MultisigWallet::new(xpub_keys?, threshold, network)?;
-            wallet.save()?;
+            if let Some(path) = xpubs_file {
+                for key in read_keys_file(&path)? {
+                    if !xpubs.contains(&key) {
+                        xpubs.push(key);
+                    }
+                }
+            }
+            
+            let wallet = MultisigWallet::new_from_keys(&xpubs, threshold, network)?;
+            wallet.save_with_overwrite(force)?;
             println!("Wallet created and saved successfully!");
             println!("Descriptor: {}", wallet.descriptor);
         }
         Commands::GetAddress { wallet } => {
             let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
-            let wallet = MultisigWallet::load(wallet_path)?;
+            let mut wallet = MultisigWallet::load(wallet_path)?;
             let address = wallet.get_new_address()?;
             println!("New address: {}", address);
         }
-        Commands::GetBalance { wallet } => {
+        Commands::GetBalance { wallet, refresh, quick } => {
             let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
             let wallet = MultisigWallet::load(wallet_path)?;
-            let balance = wallet.get_balance()?;
-            println!("Balance: {} sats", balance);
+            let balance = if refresh {
+                wallet.sync_wallet_full()?.balance().total().to_sat()
+            } else if quick {
+                wallet.sync_wallet_quick()?.balance().total().to_sat()
+            } else {
+                wallet.get_balance()?
+            };
+            println!("Balance: {} ({} sats)", format_btc(Amount::from_sat(balance)), balance);
+        }
+        Commands::Status { wallet, json, refresh, quick } => {
+            let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
+            let wallet = MultisigWallet::load(wallet_path)?;
+            let status = if refresh {
+                wallet.status_of(&wallet.sync_wallet_full()?)?
+            } else if quick {
+                wallet.status_of(&wallet.sync_wallet_quick()?)?
+            } else {
+                wallet.status()?
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                println!("{}", status);
+            }
+        }
+        Commands::ExportDescriptor { wallet } => {
+            let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
+            let wallet = MultisigWallet::load(wallet_path)?;
+            println!("{}", wallet.export_descriptor()?);
+        }
+        Commands::ImportDescriptor { descriptor, network, wallet, force } => {
+            let network = match network {
+                Some(net) => get_network_from_string(&net)?,
+                None => get_network_from_env()?,
+            };
+
+            let mut imported = MultisigWallet::from_descriptor(&descriptor, network)?;
+            if let Some(path) = wallet {
+                imported.set_path(path);
+            }
+            imported.save_with_overwrite(force)?;
+            println!("Imported watch-only wallet to {}", imported.wallet_path.display());
+            println!("First address: {}", imported.address_at(0)?);
+        }
+        Commands::SignFile { psbt_in, psbt_out, key_file, wallet } => {
+            // Nothing here syncs, so this runs on an air-gapped machine
+            let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
+            let wallet = MultisigWallet::load(wallet_path)?;
+            let mut psbt = Psbt::deserialize(&fs::read(&psbt_in)?)?;
+
+            let secp = bitcoin::secp256k1::Secp256k1::signing_only();
+            for key in read_keys_file(&key_file)? {
+                let xpriv = Xpriv::from_str(&key)?;
+                if !wallet.sign_psbt(&mut psbt, &xpriv)? {
+                    println!("Key {} added no signatures", xpriv.fingerprint(&secp));
+                }
+            }
+
+            let progress = wallet.signing_progress(&psbt)?;
+            println!("Signatures: {} of {}", progress.collected, progress.required);
+            if progress.can_finalize && wallet.finalize_psbt(&mut psbt)? {
+                let tx = psbt.clone().extract_tx()?;
+                println!("Finalized transaction: {}", bitcoin::consensus::encode::serialize_hex(&tx));
+            }
+            fs::write(&psbt_out, psbt.serialize())?;
+            println!("Wrote PSBT to {}", psbt_out.display());
+        }
+        Commands::Beacon { keys_file, network, json } => {
+            use bitcoin_multisig_wallet::BeaconSet;
+
+            let network = match network {
+                Some(net) => get_network_from_string(&net)?,
+                None => get_network_from_env()?,
+            };
+
+            let beacon_set = BeaconSet::from_manifest(&keys_file, network)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&beacon_set)?);
+            } else {
+                for beacon in &beacon_set.beacons {
+                    println!("Beacon {}-{}: {}", beacon.pair.0, beacon.pair.1, beacon.address);
+                }
+            }
+        }
+        Commands::Send { to, amount, fee, wallet } => {
+            let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
+            let wallet = MultisigWallet::load(wallet_path)?;
+            let to = parse_address(&to, wallet.network)?;
+            let amount = parse_amount(&amount)?;
+            let fee = parse_amount(&fee)?;
+
+            let psbt = wallet.create_transaction_abs_fee(&[(to.clone(), amount)], fee)?;
+            println!("Paying {} to {} with a fee of {}", format_btc(amount), to, format_btc(fee));
+            println!("PSBT: {}", psbt);
         }
         Commands::ListTransactions { wallet } => {
             let wallet_path = wallet.unwrap_or_else(|| get_wallet_dir().join("wallet.json"));
             let wallet = MultisigWallet::load(wallet_path)?;
-            wallet.list_transactions()?;
+            let transactions = wallet.get_transactions()?;
+            println!("Found {} transactions", transactions.len());
+            for tx in transactions {
+                match tx.confirmation_height {
+                    Some(height) => println!("TXID: {} confirmed at {}", tx.txid, height),
+                    None => println!("TXID: {} unconfirmed", tx.txid),
+                }
+            }
         }
-        Commands::Test => {
+        Commands::Test { force } => {
             let network = get_network_from_env()?;
             println!("\n1. Generating key 1...");
             let keygen = KeyGenerator::new(network)?;
@@ -233,7 +415,7 @@ fn main() -> Result<()> {
                 Xpub::from_str(&key2.xpub)?,
                 Xpub::from_str(&key3.xpub)?,
             ];
-            let wallet = MultisigWallet::new(xpubs, get_default_threshold(), network)?;
+            let mut wallet = MultisigWallet::new(xpubs, get_default_threshold(), network)?;
             
             println!("\n4. Testing wallet functionality...");
             println!("Getting new address...");
@@ -242,32 +424,33 @@ fn main() -> Result<()> {
             
             println!("\nGetting wallet balance...");
             let balance = wallet.get_balance()?;
-            println!("Balance: {} sats", balance);
+            println!("Balance: {} ({} sats)", format_btc(Amount::from_sat(balance)), balance);
 
             println!("\nListing transactions...");
-            wallet.list_transactions()?;
+            println!("Found {} transactions", wallet.get_transactions()?.len());
             
             println!("\n5. Testing wallet persistence...");
             println!("Saving wallet...");
-            wallet.save()?;
+            wallet.save_with_overwrite(force)?;
             println!("Wallet saved successfully!");
         }
-        Commands::DRYRUN_1 { network_str } => {
+        Commands::DRYRUN_1 { network_str, force } => {
             use serde_json::json;
             use std::fs;
-            use bitcoin_multisig_wallet::{utilities::{generate_random_xpub_and_mnemonic, get_network_from_string}, beacon::{derive_beacon_keys, create_beacon_address}};
+            use bitcoin_multisig_wallet::{utilities::generate_random_xpub_and_mnemonic, beacon::BeaconSet};
 
             let network = get_network_from_string(&network_str.unwrap_or_else(|| "testnet".to_string()))?;
+            let key_secp = bitcoin::secp256k1::Secp256k1::signing_only();
             let keys: Vec<_> = (0..3)
-                .map(|_| generate_random_xpub_and_mnemonic(network))
-                .collect();
+                .map(|_| generate_random_xpub_and_mnemonic(&key_secp, network, bip39::Language::English))
+                .collect::<Result<_, _>>()?;
 
             let [(xpub1, mnemonic1, k1), (xpub2, mnemonic2, k2), (xpub3, mnemonic3, k3)] = keys.as_slice() else {
                 panic!("Expected exactly 3 key tuples");
             };
 
             // Build xpubs list and secrets JSON
-            let xpubs = vec![xpub1.clone(), xpub2.clone(), xpub3.clone()];
+            let xpubs = vec![*xpub1, *xpub2, *xpub3];
             let secrets = vec![
                 json!({ "xpub": xpub1.to_string(), "mnemonic": mnemonic1, "publicKey": k1.to_string() }),
                 json!({ "xpub": xpub2.to_string(), "mnemonic": mnemonic2, "publicKey": k2.to_string() }),
@@ -278,58 +461,46 @@ fn main() -> Result<()> {
                 .expect("Failed to write keys.json");
             println!("Saved keys to keys.json");
 
-            let wallet = MultisigWallet::new(xpubs, 2, network).unwrap();
-            wallet.save().expect("Failed to save wallet");
+            let mut wallet = MultisigWallet::new(xpubs, 2, network).unwrap();
+            wallet.save_with_overwrite(force)?;
 
             let addr = wallet.get_new_address().unwrap();
             let balance = wallet.get_balance().unwrap();
 
             println!("Wallet saved to: {}", wallet.wallet_path.display());
             println!("New address: {}", addr);
-            println!("Balance: {} sats", balance);
-
-            let (tweaked_pub_key_12, tweaked_pub_key_21) = derive_beacon_keys(k1, k2).unwrap();
-            let (tweaked_pub_key_13, tweaked_pub_key_31) = derive_beacon_keys(k1, k3).unwrap();
-            let (tweaked_pub_key_23, tweaked_pub_key_32) = derive_beacon_keys(k2, k3).unwrap();
-
-            let beacon_address_12 = create_beacon_address(&tweaked_pub_key_12, &tweaked_pub_key_21, network).unwrap();
-            let beacon_address_13 = create_beacon_address(&tweaked_pub_key_13, &tweaked_pub_key_31, network).unwrap();
-            let beacon_address_23 = create_beacon_address(&tweaked_pub_key_23, &tweaked_pub_key_32, network).unwrap();
+            println!("Balance: {} ({} sats)", format_btc(Amount::from_sat(balance)), balance);
 
-            println!("Beacon Address 12: {}", beacon_address_12);
-            println!("Beacon Address 13: {}", beacon_address_13);
-            println!("Beacon Address 23: {}", beacon_address_23);
-
-            let beacon_addresses = vec![
-                json!({ "beacon_address_12": beacon_address_12.to_string() }),
-                json!({ "beacon_address_13": beacon_address_13.to_string() }),
-                json!({ "beacon_address_23": beacon_address_23.to_string() }),
-            ];
+            let secp = secp256k1::Secp256k1::verification_only();
+            let beacon_set = BeaconSet::from_keys(&secp, &[*k1, *k2, *k3], network).unwrap();
+            for beacon in &beacon_set.beacons {
+                println!("Beacon Address {}-{}: {}", beacon.pair.0, beacon.pair.1, beacon.address);
+            }
 
-            fs::write("beacon.json", serde_json::to_string_pretty(&beacon_addresses).unwrap())
-                .expect("Failed to write beacon addresses.json");
+            beacon_set.save_beacons(Path::new("beacon.json"))
+                .expect("Failed to write beacon.json");
             
             println!("Wallet Descriptor: {}", wallet.descriptor);
-            println!("Wallet Network: {:?}", wallet.network);
+            println!("Wallet Network: {}", network_to_string(wallet.network));
         }
 
-        Commands::DRYRUN_2 { } => {
-            use serde_json::Value;
+        Commands::DRYRUN_2 => {
+            use bitcoin::hashes::{sha256, Hash};
+            use bitcoin_multisig_wallet::beacon::BeaconSet;
             let wallet = MultisigWallet::load(get_wallet_dir().join("wallet.json"))?;
             
             let balance = wallet.get_balance().unwrap();
             println!("Wallet balance: {} sats", balance);
             
-            let json = fs::read_to_string("./beacon.json")?;
-            let beacon_addresses:Value = serde_json::from_str(&json)?;
-            let beacon_addresses = beacon_addresses.as_array().unwrap();
-            let beacon_address_12 = beacon_addresses[0].get("beacon_address_12").and_then(|v| v.as_str()).unwrap_or("Unknown");
-            let beacon_address_13 = beacon_addresses[1].get("beacon_address_13").and_then(|v| v.as_str()).unwrap_or("Unknown");
-            let beacon_address_23 = beacon_addresses[2].get("beacon_address_23").and_then(|v| v.as_str()).unwrap_or("Unknown");
-
-            let psbt1 = wallet.create_opreturn_transaction(Address::from_str(beacon_address_12).unwrap().assume_checked());
-            let psbt2 = wallet.create_opreturn_transaction(Address::from_str(beacon_address_13).unwrap().assume_checked());
-            let psbt3 = wallet.create_opreturn_transaction(Address::from_str(beacon_address_23).unwrap().assume_checked());
+            let beacon_set = BeaconSet::load_beacons(Path::new("./beacon.json"))?;
+
+            // Tag each beacon with a hash of the wallet descriptor
+            let payload = sha256::Hash::hash(wallet.descriptor.as_bytes()).to_byte_array();
+            for beacon in &beacon_set.beacons {
+                let address = parse_address(&beacon.address, wallet.network)?;
+                let psbt = wallet.create_opreturn_transaction(address, &payload)?;
+                println!("Beacon {}-{} PSBT: {}", beacon.pair.0, beacon.pair.1, psbt);
+            }
         }
     }
     Ok(())
@@ -1,20 +1,42 @@
-use anyhow::{Result, anyhow};
-use bitcoin::{Network, bip32::{Xpub, Xpriv}};
-use bitcoin::secp256k1::{rand::{self, RngCore}, Secp256k1 as BitcoinSecp256k1};
-use bip39::Mnemonic;
-use secp256k1::{PublicKey, SecretKey, Secp256k1};
-
-pub fn generate_random_xpub_and_mnemonic(network: Network) -> (Xpub, String, PublicKey) {
-    let secp = Secp256k1::new();
-    let b_secp = BitcoinSecp256k1::new();
-    let mut seed = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut seed);
-    let xprv = Xpriv::new_master(network, &seed).unwrap();
-    let xpub = Xpub::from_priv(&b_secp, &xprv);
-    let secret_key = SecretKey::from_slice(&seed).unwrap();
-    let public_key = PublicKey::from_secret_key(&secp, &secret_key);
-    let mnemonic = Mnemonic::from_entropy(&seed).unwrap().to_string();
-    (xpub, mnemonic, public_key)
+use crate::error::{Result, WalletError};
+use bitcoin::{Address, Amount, Denomination, Network, Script, bip32::{Xpub, Xpriv}};
+use bitcoin::secp256k1::{rand::{self, RngCore}, Secp256k1, Signing};
+use bdk_wallet::miniscript::descriptor::checksum::desc_checksum;
+use bip39::{Language, Mnemonic};
+use secp256k1::PublicKey;
+use std::{fs, path::Path, str::FromStr};
+use zeroize::Zeroizing;
+
+/// Generates a random master key and the `language` mnemonic encoding its
+/// 32-byte seed. The seed is used directly as BIP32 entropy; see
+/// `xpub_from_mnemonic` for the reverse.
+pub fn generate_random_xpub_and_mnemonic<C: Signing>(
+    secp: &Secp256k1<C>,
+    network: Network,
+    language: Language,
+) -> Result<(Xpub, String, PublicKey)> {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    rand::thread_rng().fill_bytes(&mut *seed);
+    let xprv = Xpriv::new_master(network, &*seed)?;
+    let xpub = Xpub::from_priv(secp, &xprv);
+    let secret_key = bitcoin::secp256k1::SecretKey::from_slice(&*seed)
+        .map_err(|e| WalletError::Key(e.to_string()))?;
+    // Beacons take keys from the standalone secp256k1 crate
+    let public_key = PublicKey::from_slice(&secret_key.public_key(secp).serialize())
+        .map_err(|e| WalletError::Key(e.to_string()))?;
+    let mnemonic = Mnemonic::from_entropy_in(language, &*seed)
+        .map_err(|e| WalletError::Key(format!("Invalid {:?} mnemonic: {}", language, e)))?
+        .to_string();
+    Ok((xpub, mnemonic, public_key))
+}
+
+/// Recovers the master xpub from a mnemonic made by
+/// `generate_random_xpub_and_mnemonic`. The phrase must be valid in `language`.
+pub fn xpub_from_mnemonic<C: Signing>(secp: &Secp256k1<C>, phrase: &str, language: Language, network: Network) -> Result<Xpub> {
+    let mnemonic = Mnemonic::parse_in(language, phrase)
+        .map_err(|e| WalletError::Key(format!("Invalid {:?} mnemonic: {}", language, e)))?;
+    let xprv = Xpriv::new_master(network, &mnemonic.to_entropy())?;
+    Ok(Xpub::from_priv(secp, &xprv))
 }
 
 pub fn get_network_from_string(network: &str) -> Result<Network> {
@@ -23,6 +45,203 @@ pub fn get_network_from_string(network: &str) -> Result<Network> {
         "testnet" => Ok(Network::Testnet),
         "signet" => Ok(Network::Signet),
         "regtest" => Ok(Network::Regtest),
-        _ => Err(anyhow!("Unsupported network: {}", network)),
+        _ => Err(WalletError::UnsupportedNetwork(network.to_string())),
+    }
+}
+
+/// Canonical lowercase name of `network`, accepted by `get_network_from_string`.
+pub fn network_to_string(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "bitcoin",
+        Network::Testnet => "testnet",
+        Network::Signet => "signet",
+        Network::Regtest => "regtest",
+        _ => "unknown",
+    }
+}
+
+/// BIP44 coin type for `network`: 0 for mainnet, 1 for every test network.
+pub fn coin_type(network: Network) -> Result<u32> {
+    match network {
+        Network::Bitcoin => Ok(0),
+        Network::Testnet | Network::Signet | Network::Regtest => Ok(1),
+        network => Err(WalletError::UnsupportedNetwork(network.to_string())),
+    }
+}
+
+/// Formats `amount` in BTC without trailing zeros, e.g. "0.0001 BTC".
+pub fn format_btc(amount: Amount) -> String {
+    format!("{} BTC", amount.to_string_in(Denomination::Bitcoin))
+}
+
+/// Parses a user-entered amount: "10000sat", "10000 sats", "0.0001 BTC", or
+/// a bare number, which is BTC as in `bitcoin-cli`.
+pub fn parse_amount(s: &str) -> Result<Amount> {
+    let input = s.trim().to_ascii_lowercase();
+    let (value, denomination) = if let Some(value) = input.strip_suffix("sats").or_else(|| input.strip_suffix("sat")) {
+        (value.trim_end(), Denomination::Satoshi)
+    } else if let Some(value) = input.strip_suffix("btc") {
+        (value.trim_end(), Denomination::Bitcoin)
+    } else {
+        (input.as_str(), Denomination::Bitcoin)
+    };
+    Amount::from_str_in(value, denomination).map_err(|e| WalletError::InvalidAmount(format!("'{}': {}", s, e)))
+}
+
+/// Parses an address string and checks that it belongs to `network`.
+pub fn parse_address(s: &str, network: Network) -> Result<Address> {
+    Address::from_str(s)?
+        .require_network(network)
+        .map_err(|_| WalletError::NetworkMismatch { expected: network })
+}
+
+/// Computes the 8-character checksum of a descriptor. Any existing
+/// `#checksum` suffix is ignored.
+pub fn descriptor_checksum(descriptor: &str) -> Result<String> {
+    let body = descriptor.split('#').next().unwrap_or(descriptor);
+    desc_checksum(body).map_err(|e| WalletError::Descriptor(e.to_string()))
+}
+
+/// Returns the descriptor with its `#checksum` suffix, replacing any existing one.
+pub fn with_checksum(descriptor: &str) -> Result<String> {
+    let body = descriptor.split('#').next().unwrap_or(descriptor);
+    Ok(format!("{}#{}", body, descriptor_checksum(body)?))
+}
+
+/// Smallest standard value for an output paying `script`, at the default
+/// dust relay fee of 3 sat/vB (e.g. 294 sats for P2WPKH, 330 for P2WSH).
+pub fn dust_threshold(script: &Script) -> Amount {
+    script.minimal_non_dust()
+}
+
+/// Rejects an output of `amount` to `script` that would be dust.
+pub fn check_not_dust(script: &Script, amount: Amount) -> Result<()> {
+    let limit = dust_threshold(script);
+    if amount < limit {
+        return Err(WalletError::BelowDust { amount, limit });
+    }
+    Ok(())
+}
+
+/// Reads descriptor keys from a file holding either a JSON array of
+/// strings or one key per line. Blank lines and `#` comments are skipped
+/// and duplicates dropped; keys may carry `[fingerprint/path]` origins.
+pub fn read_keys_file(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)?;
+    let keys: Vec<String> = match serde_json::from_str(&content) {
+        Ok(keys) => keys,
+        Err(_) => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+    };
+
+    let mut unique = Vec::new();
+    for key in keys {
+        if !unique.contains(&key) {
+            unique.push(key);
+        }
     }
-}
\ No newline at end of file
+    Ok(unique)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address_testnet() {
+        let addr = parse_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Testnet).unwrap();
+        assert_eq!(addr.to_string(), "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx");
+    }
+
+    #[test]
+    fn test_parse_address_wrong_network() {
+        let result = parse_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Testnet);
+        assert!(matches!(result, Err(WalletError::NetworkMismatch { expected: Network::Testnet })));
+    }
+
+    #[test]
+    fn test_parse_address_garbage() {
+        assert!(parse_address("not-an-address", Network::Testnet).is_err());
+    }
+
+    #[test]
+    fn test_descriptor_checksum() {
+        assert_eq!(descriptor_checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap(), "02wpgw69");
+        assert_eq!(
+            with_checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)#zzzzzzzz").unwrap(),
+            "addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)#02wpgw69"
+        );
+        assert!(descriptor_checksum("wsh(\u{e9})").is_err());
+    }
+
+    #[test]
+    fn test_dust_thresholds() {
+        use bitcoin::{hashes::Hash, ScriptBuf, WPubkeyHash, WScriptHash};
+
+        let p2wpkh = ScriptBuf::new_p2wpkh(&WPubkeyHash::all_zeros());
+        let p2wsh = ScriptBuf::new_p2wsh(&WScriptHash::all_zeros());
+        let p2tr = Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0")
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+
+        assert_eq!(dust_threshold(&p2wpkh), Amount::from_sat(294));
+        assert_eq!(dust_threshold(&p2wsh), Amount::from_sat(330));
+        assert_eq!(dust_threshold(&p2tr), Amount::from_sat(330));
+
+        assert!(check_not_dust(&p2wsh, Amount::from_sat(330)).is_ok());
+        assert!(matches!(
+            check_not_dust(&p2wsh, Amount::from_sat(329)),
+            Err(WalletError::BelowDust { limit, .. }) if limit == Amount::from_sat(330)
+        ));
+    }
+
+    #[test]
+    fn test_network_string_round_trip() {
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            let name = network_to_string(network);
+            assert_eq!(name, name.to_lowercase());
+            assert_eq!(get_network_from_string(name).unwrap(), network);
+        }
+    }
+
+    #[test]
+    fn test_coin_type() {
+        assert_eq!(coin_type(Network::Bitcoin).unwrap(), 0);
+        for network in [Network::Testnet, Network::Signet, Network::Regtest] {
+            assert_eq!(coin_type(network).unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn test_japanese_mnemonic_round_trip() {
+        let secp = Secp256k1::new();
+        let (xpub, mnemonic, _) = generate_random_xpub_and_mnemonic(&secp, Network::Testnet, Language::Japanese).unwrap();
+        assert_eq!(Mnemonic::parse_in(Language::Japanese, &mnemonic).unwrap().word_count(), 24);
+
+        assert_eq!(xpub_from_mnemonic(&secp, &mnemonic, Language::Japanese, Network::Testnet).unwrap(), xpub);
+        assert!(matches!(
+            xpub_from_mnemonic(&secp, &mnemonic, Language::English, Network::Testnet),
+            Err(WalletError::Key(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_and_parse_amounts() {
+        assert_eq!(format_btc(Amount::from_sat(10_000)), "0.0001 BTC");
+        assert_eq!(format_btc(Amount::ONE_BTC), "1 BTC");
+
+        let expected = Amount::from_sat(10_000);
+        for input in ["0.0001", "0.0001 BTC", "0.0001btc", "10000sat", "10000 sats", " 10000 SATS "] {
+            assert_eq!(parse_amount(input).unwrap(), expected, "{}", input);
+        }
+
+        for input in ["", "sats", "ten btc", "1.5 sat", "-1 BTC", "0.000000001 BTC", "100 eur"] {
+            assert!(matches!(parse_amount(input), Err(WalletError::InvalidAmount(_))), "{}", input);
+        }
+    }
+}
@@ -1,160 +1,3943 @@
-use anyhow::{anyhow, Result};
-use bitcoin::{bip32::Xpub, Address, Amount, Network, Psbt};
+use crate::encryption;
+use crate::error::{Result, WalletError};
+use crate::utilities::{check_not_dust, descriptor_checksum, network_to_string, with_checksum};
+use bitcoin::{
+    absolute, hashes::{sha256, Hash}, bip32::{ChildNumber, DerivationPath, Fingerprint, Xpriv, Xpub}, consensus::encode::serialize_hex, relative, script::PushBytesBuf, Address, Amount,
+    BlockHash, Denomination, FeeRate, Network, NetworkKind, OutPoint, Psbt, ScriptBuf, Transaction, Txid, Weight,
+};
 use bdk_wallet::{
-    bitcoin as bdk_bitcoin, descriptor::{Descriptor, DescriptorPublicKey}, CreateParams, KeychainKind, Wallet, WalletTx
+    bitcoin as bdk_bitcoin, descriptor::{Descriptor, DescriptorPublicKey}, ChangeSet, CreateParams, KeychainKind,
+    LocalOutput, SignOptions, TxBuilder, Wallet, WalletTx,
+    coin_selection::DefaultCoinSelectionAlgorithm,
+    miniscript::{
+        descriptor::{DescriptorXKey, ShInner, Wildcard, WshInner},
+        translate_hash_clone, ForEachKey, TranslateErr, TranslatePk, Translator,
+        policy::{semantic::Policy as SemanticPolicy, Liftable},
+        Terminal,
+    },
+    chain::{spk_client::FullScanRequest, ChainPosition, Merge},
+    error::CreateTxError,
 };
+use bip39::Mnemonic;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{
+    cell::{Cell, OnceCell},
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
 use esplora_client::Builder;
+use bitcoin::secp256k1::{rand::{self, Rng}, All, Secp256k1};
 use bdk_esplora::{esplora_client, EsploraExt};
 
+pub mod templates;
+
 const STOP_GAP: usize = 50;
+/// Stop gap of `sync_wallet_quick`
+const QUICK_STOP_GAP: usize = 5;
 const PARALLEL_REQUESTS: usize = 1;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+/// Confirmation target, in blocks, for the fee of transactions built
+/// without an explicit fee, such as beacon transactions
+pub const DEFAULT_CONFIRMATION_TARGET: u16 = 6;
+/// Addresses checked past the stop gap when looking for missed activity
+const GAP_PROBE_LIMIT: usize = 100;
+/// Non-hardened derivation indexes are below 2^31
+const MAX_ADDRESS_INDEX: u32 = 1 << 31;
+/// Largest OP_RETURN payload relayed by default policy
+pub const MAX_OP_RETURN_SIZE: usize = 80;
+/// Value of the output paying a beacon address; above the dust limit of
+/// every standard script type
+const BEACON_OUTPUT_SATS: u64 = 546;
+
+/// Depth of a BIP48 account-level key: m/48'/coin'/account'/script'
+const ACCOUNT_KEY_DEPTH: u8 = 4;
+
+/// Version line of BIP-129 descriptor records
+const BSMS_VERSION: &str = "BSMS 1.0";
+
+/// Last index Bitcoin Core is asked to import for ranged descriptors
+const CORE_IMPORT_RANGE_END: u32 = 999;
+
+/// Blocks a coinbase output must wait before it can be spent
+const COINBASE_MATURITY: u32 = 100;
+
+/// Current version of the on-disk wallet format
+const WALLET_FILE_VERSION: u32 = 1;
+
+/// Derivation appended to bare xpubs: receive branch, any index
+const DEFAULT_KEY_SUFFIX: &str = "/0/*";
+
+/// On-disk representation of a wallet. Files written before versioning
+/// have no `version` field and deserialize as version 0.
+#[derive(Debug, Serialize, Deserialize)]
+struct WalletFileV1 {
+    #[serde(default)]
+    version: u32,
+    descriptor: String,
+    /// Separate change descriptor of a `new_dual` wallet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    change_descriptor: Option<String>,
+    network: Network,
+    /// Highest receive index handed out by `get_new_address`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_revealed_index: Option<u32>,
+}
+
+/// Changeset cache file, tagged with the descriptor it was synced for.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedChangeset {
+    /// SHA-256 of the descriptor, hex
+    descriptor_hash: String,
+    changeset: ChangeSet,
+}
+
+/// Cosigner key export, as written by Coldcard or in a generic
+/// `{xpub, fingerprint, path}` shape.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CosignerExport {
+    Coldcard { xfp: String, p2wsh: String, p2wsh_deriv: String },
+    Generic { xpub: String, fingerprint: String, path: String },
+}
+
+impl CosignerExport {
+    fn into_key(self) -> Result<(Fingerprint, DerivationPath, Xpub)> {
+        let (fingerprint, xpub, path) = match self {
+            CosignerExport::Coldcard { xfp, p2wsh, p2wsh_deriv } => (xfp, p2wsh, p2wsh_deriv),
+            CosignerExport::Generic { xpub, fingerprint, path } => (fingerprint, xpub, path),
+        };
+        let fingerprint = Fingerprint::from_str(&fingerprint)
+            .map_err(|e| WalletError::Key(format!("Invalid fingerprint '{}': {}", fingerprint, e)))?;
+        let path = DerivationPath::from_str(&path)?;
+        let xpub = Xpub::from_str(&xpub).map_err(|e| WalletError::InvalidXpub(format!("'{}': {}", xpub, e)))?;
+        Ok((fingerprint, path, xpub))
+    }
+}
+
+/// Summary of a wallet transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxSummary {
+    pub txid: Txid,
+    /// Sats received by the wallet
+    pub received: u64,
+    /// Sats spent from the wallet
+    pub sent: u64,
+    /// Fee in sats, if all inputs are known
+    pub fee: Option<u64>,
+    /// Confirmation height, `None` while unconfirmed
+    pub confirmation_height: Option<u32>,
+    /// Consensus-encoded transaction, when requested
+    pub raw_hex: Option<String>,
+}
+
+/// Unspent output of the wallet, for coin control.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub value_sats: u64,
+    pub keychain: KeychainKind,
+    /// Derivation index of the receiving address within `keychain`
+    pub derivation_index: u32,
+    /// Confirmation height, `None` while unconfirmed
+    pub confirmation_height: Option<u32>,
+}
+
+/// Output script type of a wallet, taken from the descriptor's outer fragment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    /// Native segwit multisig, `wsh(...)`
+    P2wsh,
+    /// Nested segwit multisig, `sh(wsh(...))`
+    P2shP2wsh,
+    /// Taproot, `tr(...)`
+    P2tr,
+}
+
+impl ScriptType {
+    fn detect(desc: &Descriptor<DescriptorPublicKey>) -> Result<Self> {
+        match desc {
+            Descriptor::Wsh(_) => Ok(ScriptType::P2wsh),
+            Descriptor::Sh(sh) if matches!(sh.as_inner(), ShInner::Wsh(_)) => Ok(ScriptType::P2shP2wsh),
+            Descriptor::Tr(_) => Ok(ScriptType::P2tr),
+            other => Err(WalletError::Descriptor(format!(
+                "Unsupported descriptor type {:?}; expected wsh, sh(wsh) or tr",
+                other.desc_type()
+            ))),
+        }
+    }
+}
+
+/// Chain data source a wallet syncs from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// Esplora HTTP API at the given base URL
+    Esplora(String),
+}
+
+/// Full scan progress, reported once per script checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Keychain being scanned
+    pub keychain: KeychainKind,
+    /// Derivation index of the current script
+    pub index: u32,
+    /// Scripts checked so far across all keychains
+    pub scripts_processed: usize,
+}
+
+/// One-shot overview of a synced wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalletStatus {
+    pub network: Network,
+    /// Signing policy, e.g. `2-of-3`
+    pub policy: String,
+    pub confirmed_sats: u64,
+    /// Pending balance, trusted and untrusted
+    pub unconfirmed_sats: u64,
+    pub utxo_count: usize,
+    pub tx_count: usize,
+    /// Height of the latest synced block
+    pub sync_height: u32,
+}
+
+impl fmt::Display for WalletStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<14} {}", "Network", network_to_string(self.network))?;
+        writeln!(f, "{:<14} {}", "Policy", self.policy)?;
+        writeln!(f, "{:<14} {} sats", "Confirmed", self.confirmed_sats)?;
+        writeln!(f, "{:<14} {} sats", "Unconfirmed", self.unconfirmed_sats)?;
+        writeln!(f, "{:<14} {}", "UTXOs", self.utxo_count)?;
+        writeln!(f, "{:<14} {}", "Transactions", self.tx_count)?;
+        write!(f, "{:<14} {}", "Sync height", self.sync_height)
+    }
+}
+
+/// Usage of one address in a keychain, as listed by `keychain_report`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressStatus {
+    pub index: u32,
+    pub address: Address,
+    /// Whether the index has been handed out or seen on chain
+    pub revealed: bool,
+    /// Whether any transaction paid the address
+    pub used: bool,
+    /// Total sats received, including spent outputs
+    pub received_sats: u64,
+}
+
+/// Outputs of a transaction that look wrong before signing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxVerification {
+    /// Indexes of outputs whose script is not a standard payment script
+    /// or OP_RETURN
+    pub nonstandard_outputs: Vec<usize>,
+    /// OP_RETURN outputs carrying value, which is burned
+    pub burned_outputs: Vec<(usize, Amount)>,
+}
+
+impl TxVerification {
+    /// Whether nothing was flagged.
+    pub fn is_clean(&self) -> bool {
+        self.nonstandard_outputs.is_empty() && self.burned_outputs.is_empty()
+    }
+}
+
+/// Signature collection status of a PSBT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningProgress {
+    /// Fewest signatures collected on any input
+    pub collected: usize,
+    /// Signatures required by the descriptor
+    pub required: usize,
+    /// Whether every input has enough signatures to finalize
+    pub can_finalize: bool,
+}
+
+/// How the change address index is chosen when building transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangePolicy {
+    /// Lowest unused change index
+    #[default]
+    Sequential,
+    /// Random unused index within `STOP_GAP` of the lowest, so a full scan
+    /// still finds it
+    Random,
+}
+
+/// Guards against fee typos such as 5000 sat/vB instead of 5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeLimits {
+    /// Highest fee rate accepted
+    pub max_fee_rate: FeeRate,
+    /// Highest absolute fee accepted, as a percentage of the amount sent
+    pub max_fee_percent: u64,
+    /// Skips both checks
+    pub allow_high_fee: bool,
+    /// Floor for estimated fee rates, and the rate used when the backend
+    /// has no estimate
+    pub min_fee_rate: FeeRate,
+}
+
+impl Default for FeeLimits {
+    fn default() -> Self {
+        FeeLimits {
+            max_fee_rate: FeeRate::from_sat_per_vb_unchecked(1000),
+            max_fee_percent: 10,
+            allow_high_fee: false,
+            min_fee_rate: FeeRate::BROADCAST_MIN,
+        }
+    }
+}
+
+impl FeeLimits {
+    fn check_fee_rate(&self, fee_rate: FeeRate) -> Result<()> {
+        if !self.allow_high_fee && fee_rate > self.max_fee_rate {
+            return Err(WalletError::InvalidFee(format!(
+                "fee rate {} exceeds the maximum {}; set allow_high_fee to override",
+                fee_rate, self.max_fee_rate
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_absolute_fee(&self, fee: Amount, sent: Amount) -> Result<()> {
+        let max_fee = sent.checked_mul(self.max_fee_percent).map_or(Amount::MAX_MONEY, |max| max / 100);
+        if !self.allow_high_fee && fee > max_fee {
+            return Err(WalletError::InvalidFee(format!(
+                "fee {} is more than {}% of the amount sent {}; set allow_high_fee to override",
+                fee, self.max_fee_percent, sent
+            )));
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MultisigWallet {
     pub descriptor: String,
+    /// Change descriptor, when change uses different xpubs than receive
+    /// (see `new_dual`); otherwise change comes from `descriptor` too
+    #[serde(default)]
+    pub change_descriptor: Option<String>,
     pub network: Network,
+    /// Highest receive index handed out by `get_new_address`, saved in the
+    /// wallet file so addresses aren't handed out twice
+    #[serde(default)]
+    last_revealed_index: Option<u32>,
     #[serde(skip)]
     pub wallet_path: PathBuf,
+    /// Whether the wallet was loaded from or saved to `wallet_path`; only
+    /// then does `get_new_address` write its index back
+    #[serde(skip)]
+    on_disk: Cell<bool>,
+    /// Parsed form of `descriptor`, filled on first use
+    #[serde(skip)]
+    parsed_descriptor: OnceCell<Descriptor<DescriptorPublicKey>>,
+    /// How transaction builders pick the change address
+    #[serde(skip)]
+    pub change_policy: ChangePolicy,
+    /// Bounds applied to fees requested from transaction builders
+    #[serde(skip)]
+    pub fee_limits: FeeLimits,
+    /// Timeout for each Esplora request, in seconds
+    #[serde(skip)]
+    pub request_timeout_secs: u64,
+    /// Esplora server overriding the network default; required on regtest
+    #[serde(skip)]
+    pub esplora_url: Option<String>,
+    /// nLockTime for built transactions; unset means the current tip
+    /// height, as anti-fee-sniping
+    #[serde(skip)]
+    pub locktime: Option<absolute::LockTime>,
+    /// Context shared by every key derivation and signature
+    #[serde(skip)]
+    secp: Secp256k1<All>,
 }
 
 impl MultisigWallet {
+    /// Creates a P2WSH multisig wallet. Logs a warning for any xpub that
+    /// doesn't look like an account-level key; see `check_key_depths`.
     pub fn new(xpubs: Vec<Xpub>, threshold: usize, network: Network) -> Result<Self> {
-        let desc_str = Self::descriptor_from_xpubs(xpubs, threshold)?;
-        let desc = Descriptor::<DescriptorPublicKey>::from_str(&desc_str)?;
-        let descriptor = desc.to_string();
+        Self::new_with_script_type(xpubs, threshold, network, ScriptType::P2wsh)
+    }
+
+    /// Like `new`, but without the key depth check, for keys deliberately
+    /// taken from a non-BIP48 path.
+    pub fn new_unchecked_depth(xpubs: Vec<Xpub>, threshold: usize, network: Network) -> Result<Self> {
+        let desc_str = Self::descriptor_from_xpubs(xpubs, threshold, ScriptType::P2wsh, DEFAULT_KEY_SUFFIX)?;
+        Self::from_descriptor_str(&desc_str, network)
+    }
+
+    /// Creates a multisig wallet with the given output script type, e.g.
+    /// `ScriptType::P2shP2wsh` for `3...`/`2...` nested segwit addresses.
+    pub fn new_with_script_type(xpubs: Vec<Xpub>, threshold: usize, network: Network, script_type: ScriptType) -> Result<Self> {
+        if let Err(e) = Self::check_key_depths(&xpubs) {
+            log::warn!("{}", e);
+        }
+        let desc_str = Self::descriptor_from_xpubs(xpubs, threshold, script_type, DEFAULT_KEY_SUFFIX)?;
+        Self::from_descriptor_str(&desc_str, network)
+    }
+
+    /// Checks that every xpub sits at the BIP48 account level. A root or
+    /// otherwise mis-derived xpub still produces valid addresses, just not
+    /// the ones cosigners' hardware wallets expect.
+    pub fn check_key_depths(xpubs: &[Xpub]) -> Result<()> {
+        match xpubs.iter().find(|xpub| xpub.depth != ACCOUNT_KEY_DEPTH) {
+            Some(xpub) => Err(WalletError::UnexpectedKeyDepth { xpub: xpub.to_string(), depth: xpub.depth }),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates a P2WSH multisig whose receive and change addresses come from
+    /// separate xpubs per cosigner, e.g. exports of `account/0` and
+    /// `account/1`. `receive_xpubs[i]` and `change_xpubs[i]` must belong to
+    /// the same cosigner, i.e. share a parent fingerprint.
+    pub fn new_dual(receive_xpubs: Vec<Xpub>, change_xpubs: Vec<Xpub>, threshold: usize, network: Network) -> Result<Self> {
+        if receive_xpubs.len() != change_xpubs.len() {
+            return Err(WalletError::InvalidXpub(format!(
+                "{} receive xpubs but {} change xpubs",
+                receive_xpubs.len(),
+                change_xpubs.len()
+            )));
+        }
+        for (receive, change) in receive_xpubs.iter().zip(&change_xpubs) {
+            if receive.parent_fingerprint != change.parent_fingerprint {
+                return Err(WalletError::InvalidXpub(format!(
+                    "change xpub '{}' does not share a parent fingerprint with receive xpub '{}'",
+                    change, receive
+                )));
+            }
+        }
+
+        let change_str = Self::descriptor_from_xpubs(change_xpubs, threshold, ScriptType::P2wsh, "/*")?;
+        let change_descriptor = Descriptor::<DescriptorPublicKey>::from_str(&change_str)?.to_string();
+        let receive_str = Self::descriptor_from_xpubs(receive_xpubs, threshold, ScriptType::P2wsh, "/*")?;
+        let mut wallet = Self::from_descriptor_str(&receive_str, network)?;
+        wallet.change_descriptor = Some(change_descriptor);
+        Ok(wallet)
+    }
+
+    /// Creates a single-key `tr(xpub/0/*)` key-spend wallet, handy for
+    /// testing and comparing fees against the multisig.
+    pub fn single_sig_taproot(xpub: Xpub, network: Network) -> Result<Self> {
+        let desc_str = format!("tr({}{})", xpub, DEFAULT_KEY_SUFFIX);
+        Self::from_descriptor_str(&desc_str, network)
+    }
+
+    /// Creates a wallet whose keys carry `[fingerprint/path]` origins, so
+    /// PSBT inputs list derivations cosigners' hardware can recognize.
+    pub fn new_with_origins(keys: Vec<(Fingerprint, DerivationPath, Xpub)>, threshold: usize, network: Network) -> Result<Self> {
+        let key_strs = keys
+            .into_iter()
+            .map(|(fingerprint, path, xpub)| {
+                if path.is_empty() {
+                    format!("[{}]{}/0/*", fingerprint, xpub)
+                } else {
+                    format!("[{}/{}]{}/0/*", fingerprint, path, xpub)
+                }
+            })
+            .collect();
+        let desc_str = Self::descriptor_from_keys(key_strs, threshold, ScriptType::P2wsh)?;
+        Self::from_descriptor_str(&desc_str, network)
+    }
+
+    /// Creates a wallet from descriptor key strings. A bare xpub gets the
+    /// default `/0/*` suffix; a key that already carries a derivation
+    /// suffix (e.g. `xpub.../1/*`) is used as given. Like `new`, logs a
+    /// warning for any xpub that doesn't look like an account-level key.
+    pub fn new_from_keys(keys: &[String], threshold: usize, network: Network) -> Result<Self> {
+        let key_strs = keys
+            .iter()
+            .map(|key| Self::normalize_key(key))
+            .collect::<Result<Vec<_>>>()?;
+        let xpubs: Vec<Xpub> = key_strs
+            .iter()
+            .filter_map(|key| match DescriptorPublicKey::from_str(key) {
+                Ok(DescriptorPublicKey::XPub(xkey)) => Some(xkey.xkey),
+                _ => None,
+            })
+            .collect();
+        if let Err(e) = Self::check_key_depths(&xpubs) {
+            log::warn!("{}", e);
+        }
+        let desc_str = Self::descriptor_from_keys(key_strs, threshold, ScriptType::P2wsh)?;
+        Self::from_descriptor_str(&desc_str, network)
+    }
+
+    /// Appends `/0/*` to keys without a derivation suffix and rejects keys
+    /// that cannot derive a range of addresses.
+    fn normalize_key(key_str: &str) -> Result<String> {
+        let key = DescriptorPublicKey::from_str(key_str)
+            .map_err(|e| WalletError::InvalidXpub(format!("'{}': {}", key_str, e)))?;
+        match &key {
+            DescriptorPublicKey::Single(_) => {
+                Err(WalletError::InvalidXpub(format!("'{}' is not an extended key", key_str)))
+            }
+            DescriptorPublicKey::XPub(xkey) if xkey.derivation_path.is_empty() && xkey.wildcard == Wildcard::None => {
+                Ok(format!("{}/0/*", key_str))
+            }
+            _ if !key.has_wildcard() => {
+                Err(WalletError::InvalidXpub(format!("'{}' must end in a /* wildcard", key_str)))
+            }
+            _ => Ok(key_str.to_string()),
+        }
+    }
+
+    /// Creates a wallet from cosigner JSON exports, one file per cosigner.
+    pub fn from_cosigner_files(paths: &[PathBuf], threshold: usize, network: Network) -> Result<Self> {
+        let keys = paths
+            .iter()
+            .map(|path| {
+                let json = fs::read_to_string(path)?;
+                let export: CosignerExport = serde_json::from_str(&json)?;
+                export.into_key()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new_with_origins(keys, threshold, network)
+    }
+
+    /// Creates a wallet without touching the filesystem. `save` fails until
+    /// a location is chosen with `set_path`.
+    pub fn new_ephemeral(xpubs: Vec<Xpub>, threshold: usize, network: Network) -> Result<Self> {
+        let desc_str = Self::descriptor_from_xpubs(xpubs, threshold, ScriptType::P2wsh, DEFAULT_KEY_SUFFIX)?;
+        Self::from_descriptor_str_at(&desc_str, network, PathBuf::new())
+    }
+
+    /// Imports an existing descriptor, e.g. one exported from Sparrow or
+    /// another BDK wallet. A `#checksum` suffix, if present, must be valid.
+    /// The script type is detected from the outer fragment; only `wsh`,
+    /// `sh(wsh)` and `tr` are accepted, and extended keys must belong to
+    /// `network`.
+    pub fn from_descriptor(descriptor: &str, network: Network) -> Result<Self> {
+        let wallet = Self::from_descriptor_str(descriptor, network)?;
+        let expected = NetworkKind::from(network);
+        let keys_match = wallet.parsed_descriptor()?.for_each_key(|key| match key {
+            DescriptorPublicKey::XPub(xkey) => xkey.xkey.network == expected,
+            DescriptorPublicKey::MultiXPub(xkey) => xkey.xkey.network == expected,
+            DescriptorPublicKey::Single(_) => true,
+        });
+        if !keys_match {
+            return Err(WalletError::NetworkMismatch { expected: network });
+        }
+        Ok(wallet)
+    }
+
+    /// The descriptor's 8-character BIP-380 checksum, for comparing against
+    /// the one shown by another wallet.
+    pub fn descriptor_checksum(&self) -> String {
+        descriptor_checksum(&self.descriptor).expect("descriptor is validated when the wallet is built or loaded")
+    }
+
+    /// Public descriptor with its checksum, for importing elsewhere.
+    pub fn export_descriptor(&self) -> Result<String> {
+        with_checksum(&self.descriptor)
+    }
+
+    /// Change descriptor matching the receive descriptor: every key's final
+    /// `/0/*` step becomes `/1/*`. `None` if some key doesn't end in `/0/*`.
+    fn internal_descriptor(&self) -> Result<Option<String>> {
+        if let Some(change) = &self.change_descriptor {
+            return Ok(Some(with_checksum(change)?));
+        }
+        match self.parsed_descriptor()?.translate_pk(&mut ChangeBranch) {
+            Ok(internal) => Ok(Some(internal.to_string())),
+            Err(TranslateErr::TranslatorErr(())) => Ok(None),
+            Err(TranslateErr::OuterError(e)) => Err(WalletError::Descriptor(e.to_string())),
+        }
+    }
+
+    /// JSON argument for Bitcoin Core's `importdescriptors`, watching the
+    /// receive and (when derivable) change descriptors. `timestamp` is
+    /// `"now"` or a UNIX time to rescan from.
+    pub fn core_import_descriptors(&self, timestamp: &str) -> Result<String> {
+        let timestamp = match timestamp {
+            "now" => serde_json::Value::from("now"),
+            other => serde_json::Value::from(other.parse::<u64>().map_err(|_| {
+                WalletError::Descriptor(format!("timestamp must be \"now\" or a UNIX time, got '{}'", other))
+            })?),
+        };
+        let ranged = self.parsed_descriptor()?.has_wildcard();
+        let entry = |desc: String, internal: bool| {
+            let mut entry = serde_json::json!({
+                "desc": desc,
+                "timestamp": timestamp,
+                "internal": internal,
+            });
+            if ranged {
+                entry["active"] = true.into();
+                entry["range"] = serde_json::json!([0, CORE_IMPORT_RANGE_END]);
+            }
+            entry
+        };
+
+        let mut entries = vec![entry(self.export_descriptor()?, false)];
+        if let Some(internal) = self.internal_descriptor()? {
+            entries.push(entry(internal, true));
+        }
+        Ok(serde_json::to_string_pretty(&entries)?)
+    }
 
+    /// BIP-129 (BSMS) round-2 descriptor record for importing into Sparrow
+    /// and other coordinators: version, descriptor, path restrictions and
+    /// first address, one per line. Keys ending in `/0/*` are written in the
+    /// `/**` template form covering receive and change.
+    pub fn export_bsms(&self) -> Result<String> {
+        // A separate change descriptor can't be expressed as a template
+        let (descriptor, restrictions) = if self.change_descriptor.is_none() && self.internal_descriptor()?.is_some() {
+            let external = self.parsed_descriptor()?.to_string();
+            let template = external.split('#').next().unwrap_or_default().replace("/0/*", "/**");
+            (with_checksum(&template)?, "/0/*,/1/*")
+        } else {
+            (self.export_descriptor()?, "No path restrictions")
+        };
+        Ok(format!("{}\n{}\n{}\n{}", BSMS_VERSION, descriptor, restrictions, self.fingerprint_address()?))
+    }
+
+    /// Creates a wallet stored as `wallet-<name>.json` in the wallet
+    /// directory, so several wallets can live side by side.
+    pub fn new_named(xpubs: Vec<Xpub>, threshold: usize, network: Network, name: &str) -> Result<Self> {
+        let wallet_path = Self::named_wallet_path(name)?;
+        let desc_str = Self::descriptor_from_xpubs(xpubs, threshold, ScriptType::P2wsh, DEFAULT_KEY_SUFFIX)?;
+        Self::from_descriptor_str_at(&desc_str, network, wallet_path)
+    }
+
+    /// Loads the wallet saved as `wallet-<name>.json`.
+    pub fn load_named(name: &str) -> Result<Self> {
+        Self::load(Self::named_wallet_path(name)?)
+    }
+
+    /// Lists the names of all named wallets in the wallet directory.
+    pub fn list_wallets() -> Result<Vec<String>> {
+        Self::list_wallets_in(&Self::wallet_dir()?)
+    }
+
+    fn list_wallets_in(dir: &Path) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if let Some(name) = file_name.strip_prefix("wallet-").and_then(|rest| rest.strip_suffix(".json")) {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn wallet_dir() -> Result<PathBuf> {
         let wallet_dir = dirs::home_dir()
-            .ok_or_else(|| anyhow!("Could not find home directory"))?
+            .ok_or(WalletError::HomeDirNotFound)?
             .join(".bitcoin-multisig");
         fs::create_dir_all(&wallet_dir)?;
-        let wallet_path = wallet_dir.join("wallet.json");
+        Ok(wallet_dir)
+    }
+
+    fn named_wallet_path(name: &str) -> Result<PathBuf> {
+        Self::named_wallet_path_in(&Self::wallet_dir()?, name)
+    }
+
+    fn named_wallet_path_in(dir: &Path, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(WalletError::InvalidWalletName(name.to_string()));
+        }
+        Ok(dir.join(format!("wallet-{}.json", name)))
+    }
+
+    fn from_descriptor_str(desc_str: &str, network: Network) -> Result<Self> {
+        let wallet_path = Self::wallet_dir()?.join("wallet.json");
+        Self::from_descriptor_str_at(desc_str, network, wallet_path)
+    }
+
+    fn from_descriptor_str_at(desc_str: &str, network: Network, wallet_path: PathBuf) -> Result<Self> {
+        let desc = Descriptor::<DescriptorPublicKey>::from_str(desc_str)?;
+        ScriptType::detect(&desc)?;
+        let unhardened = desc.for_each_key(|key| match key {
+            DescriptorPublicKey::XPub(xkey) => xkey.wildcard != Wildcard::Hardened,
+            DescriptorPublicKey::MultiXPub(xkey) => xkey.wildcard != Wildcard::Hardened,
+            DescriptorPublicKey::Single(_) => true,
+        });
+        if !unhardened {
+            return Err(WalletError::Descriptor(
+                "hardened wildcards (/*') cannot be derived from xpubs; use /* or a fixed index".to_string(),
+            ));
+        }
+        let descriptor = desc.to_string();
+        let parsed_descriptor = OnceCell::from(desc);
+
+        Ok(Self {
+            descriptor,
+            change_descriptor: None,
+            network,
+            last_revealed_index: None,
+            wallet_path,
+            on_disk: Cell::new(false),
+            parsed_descriptor,
+            change_policy: ChangePolicy::default(),
+            fee_limits: FeeLimits::default(),
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            esplora_url: None,
+            locktime: None,
+            secp: Secp256k1::new(),
+        })
+    }
+
+    /// Whether the descriptor holds any private keys (xprv/tprv or WIF), so
+    /// the wallet could sign on its own. Descriptors written by this crate
+    /// are public-only.
+    pub fn can_sign(&self) -> bool {
+        Descriptor::parse_descriptor(&self.secp, &self.descriptor).is_ok_and(|(_, key_map)| !key_map.is_empty())
+    }
+
+    /// Public-only copy of the wallet, safe to share with cosigners. The
+    /// descriptor is re-encoded from its parsed public keys, so no extended
+    /// private key can survive; the copy has no `wallet_path`.
+    pub fn to_watch_only(&self) -> Result<MultisigWallet> {
+        let descriptor = self.parsed_descriptor()?.to_string();
+        if descriptor.contains("xprv") || descriptor.contains("tprv") {
+            return Err(WalletError::Descriptor("Descriptor still holds private key material".to_string()));
+        }
+        let mut watch_only = Self::from_descriptor_str_at(&descriptor, self.network, PathBuf::new())?;
+        watch_only.change_descriptor = self.change_descriptor.clone();
+        watch_only.last_revealed_index = self.last_revealed_index;
+        watch_only.change_policy = self.change_policy;
+        watch_only.fee_limits = self.fee_limits;
+        watch_only.request_timeout_secs = self.request_timeout_secs;
+        watch_only.esplora_url = self.esplora_url.clone();
+        watch_only.locktime = self.locktime;
+        Ok(watch_only)
+    }
+
+    /// Rebuilds the wallet with `old_xpub` swapped for `new_xpub`, e.g. after
+    /// a cosigner's key is compromised. The new key keeps the old one's
+    /// derivation suffix but no origin. The returned wallet has new addresses
+    /// and no `wallet_path`, so the original file isn't overwritten by accident.
+    pub fn replace_key(&self, old_xpub: &Xpub, new_xpub: Xpub) -> Result<MultisigWallet> {
+        if new_xpub.network != NetworkKind::from(self.network) {
+            return Err(WalletError::NetworkMismatch { expected: self.network });
+        }
+        let desc = self.parsed_descriptor()?;
+        let mut old_key = None;
+        desc.for_each_key(|key| {
+            if let DescriptorPublicKey::XPub(xkey) = key {
+                if xkey.xkey == *old_xpub {
+                    old_key = Some(xkey.clone());
+                }
+            }
+            true
+        });
+        let old_key = old_key
+            .ok_or_else(|| WalletError::InvalidXpub(format!("'{}' is not a key of this wallet", old_xpub)))?;
+        let new_key = DescriptorPublicKey::XPub(DescriptorXKey {
+            origin: None,
+            xkey: new_xpub,
+            derivation_path: old_key.derivation_path.clone(),
+            wildcard: old_key.wildcard,
+        });
 
-        Ok(Self { descriptor, network, wallet_path })
+        let desc_str = desc.to_string();
+        let desc_str = desc_str
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .replace(&DescriptorPublicKey::XPub(old_key).to_string(), &new_key.to_string());
+        Self::from_descriptor_str_at(&desc_str, self.network, PathBuf::new())
     }
 
-    fn descriptor_from_xpubs(xpubs: Vec<Xpub>, threshold: usize) -> Result<String> {
-        if threshold > xpubs.len() {
-            return Err(anyhow!("Threshold cannot exceed number of keys"));
+    /// Sets the file the wallet is saved to.
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.wallet_path = path;
+    }
+
+    fn descriptor_from_xpubs(xpubs: Vec<Xpub>, threshold: usize, script_type: ScriptType, suffix: &str) -> Result<String> {
+        Self::check_unhardened_suffix(suffix)?;
+        let key_strs = xpubs.into_iter().map(|xpub| format!("{}{}", xpub, suffix)).collect();
+        Self::descriptor_from_keys(key_strs, threshold, script_type)
+    }
+
+    /// An xpub holds no private key material, so it can only derive
+    /// non-hardened children.
+    fn check_unhardened_suffix(suffix: &str) -> Result<()> {
+        let hardened = suffix
+            .split('/')
+            .any(|step| step.ends_with('\'') || step.ends_with('h') || step.ends_with('H'));
+        if hardened {
+            return Err(WalletError::Descriptor(format!(
+                "derivation suffix '{}' has a hardened step, but xpubs can only derive non-hardened children",
+                suffix
+            )));
+        }
+        Ok(())
+    }
+
+    fn descriptor_from_keys(key_strs: Vec<String>, threshold: usize, script_type: ScriptType) -> Result<String> {
+        if threshold > key_strs.len() {
+            return Err(WalletError::ThresholdTooHigh { threshold, keys: key_strs.len() });
         }
 
-        let keys: Result<Vec<_>> = xpubs.into_iter()
-            .map(|xpub| {
-                let key_str = format!("{}/0/*", xpub);
+        let keys: Result<Vec<_>> = key_strs.into_iter()
+            .map(|key_str| {
                 DescriptorPublicKey::from_str(&key_str)
                     .map(|k| k.to_string())
-                    .map_err(|e| anyhow!("Invalid descriptor key '{}': {}", key_str, e))
+                    .map_err(|e| WalletError::InvalidXpub(format!("'{}': {}", key_str, e)))
             })
             .collect();
 
-        Ok(format!("wsh(multi({},{}))", threshold, keys?.join(",")))
+        let multi = format!("multi({},{})", threshold, keys?.join(","));
+        match script_type {
+            ScriptType::P2wsh => Ok(format!("wsh({})", multi)),
+            ScriptType::P2shP2wsh => Ok(format!("sh(wsh({}))", multi)),
+            ScriptType::P2tr => Err(WalletError::Descriptor(
+                "Taproot multisig descriptors cannot be built from xpubs".to_string(),
+            )),
+        }
+    }
+
+    /// Serializes the wallet in the current versioned file format.
+    pub fn to_json(&self) -> Result<String> {
+        let file = WalletFileV1 {
+            version: WALLET_FILE_VERSION,
+            descriptor: self.descriptor.clone(),
+            change_descriptor: self.change_descriptor.clone(),
+            network: self.network,
+            last_revealed_index: self.last_revealed_index,
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    /// Deserializes a wallet file, upgrading older formats. The returned
+    /// wallet has an empty `wallet_path`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: WalletFileV1 = serde_json::from_str(json)?;
+        if file.version > WALLET_FILE_VERSION {
+            return Err(WalletError::UnsupportedVersion(file.version));
+        }
+        // Hand-edited files are checked here so `descriptor_checksum` can't fail
+        let checksum = descriptor_checksum(&file.descriptor)?;
+        if file.descriptor.split_once('#').is_some_and(|(_, given)| given != checksum) {
+            return Err(WalletError::Descriptor(format!("descriptor checksum does not match, expected #{}", checksum)));
+        }
+        Ok(Self {
+            descriptor: file.descriptor,
+            change_descriptor: file.change_descriptor,
+            network: file.network,
+            last_revealed_index: file.last_revealed_index,
+            wallet_path: PathBuf::new(),
+            on_disk: Cell::new(false),
+            parsed_descriptor: OnceCell::new(),
+            change_policy: ChangePolicy::default(),
+            fee_limits: FeeLimits::default(),
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            esplora_url: None,
+            locktime: None,
+            secp: Secp256k1::new(),
+        })
     }
 
+    /// Writes the wallet file. Fails with `WalletConflict` rather than
+    /// replace a file holding a different wallet; see `save_with_overwrite`.
+    ///
+    /// The file is plaintext JSON, and a descriptor imported with xprivs
+    /// keeps them; use `save_encrypted` for anything holding secrets.
     pub fn save(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(&self.wallet_path, json)?;
+        self.save_with_overwrite(false)
+    }
+
+    /// Like `save`, but with `overwrite` set replaces whatever wallet is
+    /// already at the path.
+    pub fn save_with_overwrite(&self, overwrite: bool) -> Result<()> {
+        self.check_can_write(overwrite, None)?;
+        fs::write(&self.wallet_path, self.to_json()?)?;
+        self.on_disk.set(true);
+        Ok(())
+    }
+
+    /// Like `save`, but encrypts the file with AES-256-GCM under a key
+    /// derived from `passphrase`. Read it back with `load_encrypted`.
+    pub fn save_encrypted(&self, passphrase: &str) -> Result<()> {
+        self.check_can_write(false, Some(passphrase))?;
+        fs::write(&self.wallet_path, encryption::encrypt(self.to_json()?.as_bytes(), passphrase)?)?;
+        self.on_disk.set(true);
+        Ok(())
+    }
+
+    /// Makes sure saving won't replace a different wallet. An existing
+    /// encrypted file is only compared when `passphrase` is given.
+    fn check_can_write(&self, overwrite: bool, passphrase: Option<&str>) -> Result<()> {
+        if self.wallet_path.as_os_str().is_empty() {
+            return Err(WalletError::NoWalletPath);
+        }
+        if overwrite || !self.wallet_path.exists() {
+            return Ok(());
+        }
+        let data = fs::read(&self.wallet_path)?;
+        let existing = match passphrase {
+            None if encryption::is_encrypted(&data) => {
+                return Err(WalletError::WalletConflict {
+                    path: self.wallet_path.clone(),
+                    existing: "an encrypted wallet".to_string(),
+                });
+            }
+            Some(passphrase) if encryption::is_encrypted(&data) => encryption::decrypt(&data, passphrase)?,
+            _ => data,
+        };
+        let existing = Self::from_json(&String::from_utf8_lossy(&existing))?;
+        if !self.same_descriptors(&existing)? {
+            return Err(WalletError::WalletConflict {
+                path: self.wallet_path.clone(),
+                existing: existing.descriptor,
+            });
+        }
         Ok(())
     }
 
     pub fn load(path: PathBuf) -> Result<Self> {
-        let json = fs::read_to_string(&path)?;
-        let mut wallet: MultisigWallet = serde_json::from_str(&json)?;
+        let data = fs::read(&path)?;
+        if encryption::is_encrypted(&data) {
+            return Err(WalletError::Encryption(format!(
+                "{} is encrypted; use load_encrypted",
+                path.display()
+            )));
+        }
+        let mut wallet = Self::from_json(&String::from_utf8_lossy(&data))?;
+        wallet.wallet_path = path;
+        wallet.on_disk.set(true);
+        Ok(wallet)
+    }
+
+    /// Loads a file written by `save_encrypted`. A wrong passphrase fails
+    /// with `WalletError::Encryption`.
+    pub fn load_encrypted(path: PathBuf, passphrase: &str) -> Result<Self> {
+        let json = encryption::decrypt(&fs::read(&path)?, passphrase)?;
+        let mut wallet = Self::from_json(&String::from_utf8_lossy(&json))?;
         wallet.wallet_path = path;
+        wallet.on_disk.set(true);
         Ok(wallet)
     }
 
-    fn to_bdk_network(&self) -> Result<bdk_bitcoin::Network> {
-        use bdk_bitcoin::Network::*;
-        match self.network {
-            Network::Bitcoin => Ok(Bitcoin),
-            Network::Testnet => Ok(Testnet),
-            Network::Signet => Ok(Signet),
-            Network::Regtest => Ok(Regtest),
-            _ => Err(anyhow!("Unsupported network")),
+    /// Compares parsed descriptors, so checksums and formatting don't matter.
+    fn same_descriptors(&self, other: &MultisigWallet) -> Result<bool> {
+        Ok(self.network == other.network
+            && self.parsed_descriptor()? == other.parsed_descriptor()?
+            && self.parsed_change_descriptor()? == other.parsed_change_descriptor()?)
+    }
+
+    fn to_bdk_network(&self) -> bdk_bitcoin::Network {
+        // bdk_wallet re-exports the same `bitcoin` crate, so every variant maps to itself
+        self.network
+    }
+
+    /// Returns the parsed descriptor, parsing `descriptor` on first use.
+    fn parsed_descriptor(&self) -> Result<&Descriptor<DescriptorPublicKey>> {
+        if let Some(desc) = self.parsed_descriptor.get() {
+            return Ok(desc);
         }
+        let desc = Descriptor::<DescriptorPublicKey>::from_str(&self.descriptor)?;
+        Ok(self.parsed_descriptor.get_or_init(|| desc))
     }
 
-    fn create_wallet(&self) -> Result<Wallet> {
-        let descriptor = Descriptor::from_str(&self.descriptor)?;
-        let network = self.to_bdk_network()?;
-        let params = CreateParams::new_single(descriptor).network(network);
-        let wallet = params.create_wallet_no_persist()?;
-        Ok(wallet)
+    /// Number of addresses per keychain: one for a fixed (non-wildcard)
+    /// descriptor, where index 0 is the only address.
+    fn address_count(&self) -> Result<u32> {
+        Ok(if self.parsed_descriptor()?.has_wildcard() { MAX_ADDRESS_INDEX } else { 1 })
     }
 
-    pub fn get_new_address(&self) -> Result<Address> {
-        let wallet = self.create_wallet()?;
-        let script = wallet.peek_address(KeychainKind::External, 0).script_pubkey();
-        let addr = bdk_bitcoin::Address::from_script(&script, self.to_bdk_network()?)?;
-        Ok(Address::from_str(&addr.to_string())?.require_network(self.network)?)
+    /// Descriptor of the internal keychain: `change_descriptor` if set,
+    /// else the `/1/*` counterpart of a `/0/*` receive descriptor. `None`
+    /// leaves BDK sending change to receive addresses.
+    fn parsed_change_descriptor(&self) -> Result<Option<Descriptor<DescriptorPublicKey>>> {
+        self.internal_descriptor()?
+            .as_deref()
+            .map(|desc| Ok(Descriptor::<DescriptorPublicKey>::from_str(desc)?))
+            .transpose()
     }
 
-    pub fn sync_wallet(&self) -> Result<Wallet> {
-        let mut wallet = self.create_wallet()?;
-        let client_url = match self.network {
-            Network::Bitcoin => "https://blockstream.info/api/",
-            Network::Testnet => "https://blockstream.info/testnet/api/",
-            Network::Signet => "https://mempool.space/signet/api/",
-            _ => return Err(anyhow!("Unsupported network for Esplora")),
+    /// Script type of the wallet's outputs.
+    pub fn script_type(&self) -> Result<ScriptType> {
+        ScriptType::detect(self.parsed_descriptor()?)
+    }
+
+    /// Number of signatures required by the descriptor.
+    fn threshold(&self) -> Result<usize> {
+        let wsh = match self.parsed_descriptor()? {
+            Descriptor::Wsh(wsh) => wsh,
+            Descriptor::Sh(sh) => match sh.as_inner() {
+                ShInner::Wsh(wsh) => wsh,
+                _ => return Err(WalletError::Descriptor("Not a wsh multisig descriptor".to_string())),
+            },
+            _ => return Err(WalletError::Descriptor("Not a wsh multisig descriptor".to_string())),
         };
-        let client: esplora_client::BlockingClient = Builder::new(client_url).build_blocking();
+        match wsh.as_inner() {
+            WshInner::SortedMulti(smv) => Ok(smv.k()),
+            WshInner::Ms(ms) => ms
+                .iter()
+                .find_map(|node| match &node.node {
+                    Terminal::Multi(thresh) => Some(thresh.k()),
+                    _ => None,
+                })
+                .ok_or_else(|| WalletError::Descriptor("No multisig fragment in descriptor".to_string())),
+        }
+    }
 
-        let full_scan = wallet.start_full_scan();
-        let full_scan_res = client.full_scan(full_scan, STOP_GAP, PARALLEL_REQUESTS)?;
-        wallet.apply_update(full_scan_res)?;
+    /// Reports, for each private key, whether it is one of the descriptor's
+    /// cosigners. Keys with an origin are matched by deriving `xpriv` along
+    /// the origin path when the master fingerprint matches; keys without
+    /// one are compared directly.
+    pub fn keys_match_descriptor(&self, xprivs: &[Xpriv]) -> Result<Vec<bool>> {
+        let secp = &self.secp;
+        let mut desc_keys = Vec::new();
+        self.parsed_descriptor()?.for_each_key(|key| {
+            match key {
+                DescriptorPublicKey::XPub(xkey) => desc_keys.push((xkey.origin.clone(), xkey.xkey)),
+                DescriptorPublicKey::MultiXPub(xkey) => desc_keys.push((xkey.origin.clone(), xkey.xkey)),
+                DescriptorPublicKey::Single(_) => {}
+            }
+            true
+        });
 
-        let sync = wallet.start_sync_with_revealed_spks();
-        let sync_res = client.sync(sync, PARALLEL_REQUESTS)?;
-        wallet.apply_update(sync_res)?;
+        xprivs
+            .iter()
+            .map(|xpriv| {
+                for (origin, xpub) in &desc_keys {
+                    let candidate = match origin {
+                        Some((fingerprint, path)) if *fingerprint == xpriv.fingerprint(secp) => {
+                            Xpub::from_priv(secp, &xpriv.derive_priv(secp, path)?)
+                        }
+                        _ => Xpub::from_priv(secp, xpriv),
+                    };
+                    if candidate == *xpub {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            })
+            .collect()
+    }
+
+    /// Number of keys in the descriptor.
+    fn key_count(&self) -> Result<usize> {
+        let mut keys = 0;
+        self.parsed_descriptor()?.for_each_key(|_| {
+            keys += 1;
+            true
+        });
+        Ok(keys)
+    }
+
+    /// Signing policy as `m-of-n`.
+    pub fn policy(&self) -> Result<String> {
+        Ok(format!("{}-of-{}", self.threshold()?, self.key_count()?))
+    }
+
+    /// Short id for telling multisig wallets apart: the first 4 bytes, in
+    /// hex, of SHA-256 over the sorted cosigner master fingerprints and the
+    /// `m-of-n` policy. Independent of key order.
+    pub fn wallet_fingerprint(&self) -> Result<String> {
+        let mut fingerprints = Vec::new();
+        self.parsed_descriptor()?.for_each_key(|key| {
+            fingerprints.push(key.master_fingerprint().to_string());
+            true
+        });
+        fingerprints.sort();
+        let preimage = format!("{}|{}", fingerprints.join(","), self.policy()?);
+        let hash = sha256::Hash::hash(preimage.as_bytes());
+        Ok(hash.to_byte_array()[..4].iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Worst-case witness weight, in weight units, of spending one of the
+    /// wallet's multisig outputs: the item count, the empty item consumed by
+    /// the CHECKMULTISIG bug, `m` 73-byte signatures and the witness script.
+    /// For `sh(wsh(...))` the scriptSig push is not included.
+    pub fn estimated_witness_weight(&self) -> Result<usize> {
+        fn compact_size(n: usize) -> usize {
+            match n {
+                0..=0xfc => 1,
+                0xfd..=0xffff => 3,
+                _ => 5,
+            }
+        }
+        const SIGNATURE_SIZE: usize = 73;
+        const PUBKEY_PUSH_SIZE: usize = 1 + 33;
+
+        let m = self.threshold()?;
+        let n = self.key_count()?;
+        // OP_m <pubkeys> OP_n OP_CHECKMULTISIG
+        let script_size = 1 + n * PUBKEY_PUSH_SIZE + 1 + 1;
+        let items = 2 + m;
+        Ok(compact_size(items)
+            + compact_size(0)
+            + m * (compact_size(SIGNATURE_SIZE) + SIGNATURE_SIZE)
+            + compact_size(script_size)
+            + script_size)
+    }
 
+    /// Fresh in-memory BDK wallet for the descriptor, with no chain data
+    /// and no network calls.
+    pub fn create_wallet(&self) -> Result<Wallet> {
+        let descriptor = self.parsed_descriptor()?.clone();
+        let network = self.to_bdk_network();
+        log::debug!("Creating {} wallet for {}", network, self.descriptor);
+        let params = match self.parsed_change_descriptor()? {
+            Some(change_descriptor) => CreateParams::new(descriptor, change_descriptor),
+            None => CreateParams::new_single(descriptor),
+        }
+        .network(network);
+        let wallet = params.create_wallet_no_persist()?;
         Ok(wallet)
     }
 
-    pub fn get_balance(&self) -> Result<u64> {
-        Ok(self.sync_wallet()?.balance().total().to_sat())
+    /// Reveals a fresh receive address, one past both the last one handed
+    /// out and the highest index in the cached history. This advances the
+    /// wallet and writes the index back to a plaintext wallet file, so later
+    /// calls keep advancing; use `address_at` to look at an address without
+    /// handing it out.
+    pub fn get_new_address(&mut self) -> Result<Address> {
+        let wallet = self.offline_wallet()?;
+        // A fixed descriptor peeks index 0 whatever is asked for
+        let info = wallet.peek_address(KeychainKind::External, self.next_receive_index(&wallet));
+        self.last_revealed_index = Some(info.index);
+        self.persist_address_index()?;
+        Ok(info.address)
     }
 
-    pub fn list_transactions(&self) -> Result<()> {
-        // Sync the wallet to get the latest transaction data. This can fail.
-        let synced_wallet = self.sync_wallet()?;
+    /// Receive address at `index`. Doesn't reveal it, so `get_new_address`
+    /// may still hand it out later.
+    pub fn address_at(&self, index: u32) -> Result<Address> {
+        let count = self.address_count()?;
+        if index >= count {
+            return Err(WalletError::AddressIndexOutOfRange { index, count });
+        }
+        Ok(self.create_wallet()?.peek_address(KeychainKind::External, index).address)
+    }
 
-        let tx_iterator = synced_wallet.transactions(); // Returns iterator
-        let transactions: Vec<WalletTx> = tx_iterator.collect(); // Collect into Vec<WalletTx>
+    /// Lowest unused change address in the cached history. Doesn't reveal
+    /// it; building a transaction does.
+    pub fn get_change_address(&self) -> Result<Address> {
+        Ok(self.offline_wallet()?.next_unused_address(KeychainKind::Internal).address)
+    }
 
-        println!("Found {} transactions", transactions.len());
-        // Process the Vec<WalletTx> here
-        for wallet_tx in transactions {
-            println!("{} TXID: {} at {}", wallet_tx.chain_position.is_confirmed(), wallet_tx.tx_node.txid, wallet_tx.tx_node.lock_time);
-            // access wallet_tx.details.received, .sent, .fee etc.
-            // access wallet_tx.chain_position.confirmation_time() etc.
+    /// First receive index past both the last one handed out and the
+    /// highest one in `wallet`'s history.
+    fn next_receive_index(&self, wallet: &Wallet) -> u32 {
+        let handed_out = self.last_revealed_index.map_or(0, |index| index + 1);
+        let seen = wallet.derivation_index(KeychainKind::External).map_or(0, |index| index + 1);
+        handed_out.max(seen)
+    }
+
+    /// Saves the revealed index if the wallet came from, or was saved to, a
+    /// plaintext file. Fresh wallets aren't written to their default path
+    /// behind the caller's back, and an encrypted file can't be rewritten
+    /// without its passphrase, so in both cases the index only lives in
+    /// memory.
+    fn persist_address_index(&self) -> Result<()> {
+        if !self.on_disk.get() || !self.wallet_path.exists() {
+            return Ok(());
         }
-        Ok(())
+        if encryption::is_encrypted(&fs::read(&self.wallet_path)?) {
+            log::warn!("Not saving the address index to encrypted {}", self.wallet_path.display());
+            return Ok(());
+        }
+        self.save()
     }
 
-    pub fn create_opreturn_transaction(&self, send_address:Address) -> Result<Psbt> {
-        let mut wallet = self.sync_wallet()?;
-    
-        // Use a dummy address to send change (could also be same wallet)
-        let change_address = wallet.next_unused_address(KeychainKind::Internal);
+    /// Wallet restored from the changeset cache without touching the
+    /// network, or a fresh one when there is no usable cache.
+    fn offline_wallet(&self) -> Result<Wallet> {
+        if let Some(changeset) = self.load_changeset()? {
+            if let Some(wallet) = self.wallet_from_changeset(changeset)? {
+                return Ok(wallet);
+            }
+        }
+        self.create_wallet()
+    }
+
+    /// The "verification address": external index 0, derived straight from
+    /// the descriptor so it never depends on sync state. Cosigners compare it
+    /// during setup to confirm they built the same wallet.
+    pub fn fingerprint_address(&self) -> Result<Address> {
+        let wallet = self.create_wallet()?;
+        Ok(wallet.peek_address(KeychainKind::External, 0).address)
+    }
+
+    /// Lines for cosigners to check against each other before funding.
+    pub fn setup_checklist(&self) -> Result<String> {
+        Ok(format!(
+            "Network: {}\nPolicy: {}\nVerification address: {}",
+            network_to_string(self.network),
+            self.policy()?,
+            self.fingerprint_address()?
+        ))
+    }
 
-        println!("{}", self.descriptor);
-    
-        let mut data = [0u8; 76]; // Initialize with zeros
-        let bytes = self.descriptor.as_bytes();
+    /// Lazily derives `(index, address)` pairs for `keychain` without any
+    /// network calls. Callers bound the iterator with `take`.
+    pub fn address_iter(&self, keychain: KeychainKind) -> Result<impl Iterator<Item = (u32, Address)>> {
+        let wallet = self.create_wallet()?;
+        let count = self.address_count()?;
+        Ok((0..count).map(move |index| (index, wallet.peek_address(keychain, index).address)))
+    }
 
-        println!("Descriptor bytes: {:?}", bytes);
+    /// BIP-21 `bitcoin:` URIs for the next `count` unused receive addresses,
+    /// with an optional amount (in BTC) and label.
+    pub fn receive_uris(&self, count: u32, amount: Option<Amount>, label: Option<&str>) -> Result<Vec<String>> {
+        let wallet = self.sync_wallet()?;
+        self.receive_uris_in(&wallet, count, amount, label)
+    }
 
-        // Copy the bytes into the fixed-size array
-        data[..bytes.len()].copy_from_slice(bytes);
-        println!("Data bytes: {:?}", data);
+    fn receive_uris_in(&self, wallet: &Wallet, count: u32, amount: Option<Amount>, label: Option<&str>) -> Result<Vec<String>> {
+        let mut params = Vec::new();
+        if let Some(amount) = amount {
+            params.push(format!("amount={}", amount.to_string_in(Denomination::Bitcoin)));
+        }
+        if let Some(label) = label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        let query = if params.is_empty() { String::new() } else { format!("?{}", params.join("&")) };
 
-        let mut tx_builder = wallet.build_tx();
-        tx_builder.add_recipient(send_address.script_pubkey(), Amount::from_sat(546));
-        tx_builder.add_data(&data);
-        tx_builder.drain_to(change_address.script_pubkey()); // drain remaining funds to change
-    
-        let psbt: bitcoin::Psbt = tx_builder.finish()?;
-        Ok(psbt)
+        let start = self.next_receive_index(wallet);
+        Ok((start..self.address_count()?)
+            .filter(|index| !wallet.spk_index().is_used(KeychainKind::External, *index))
+            .take(count as usize)
+            .map(|index| format!("bitcoin:{}{}", wallet.peek_address(KeychainKind::External, index).address, query))
+            .collect())
     }
-}
\ No newline at end of file
+
+    /// Derives the script pubkeys for `range` on `keychain` straight from the
+    /// descriptor, skipping address encoding. No network calls. A fixed
+    /// descriptor only has index 0, so the range is cut off there.
+    pub fn derive_script_pubkeys(&self, keychain: KeychainKind, range: Range<u32>) -> Result<Vec<ScriptBuf>> {
+        let wallet = self.create_wallet()?;
+        let descriptor = wallet.public_descriptor(keychain);
+        let end = range.end.min(self.address_count()?);
+        (range.start..end)
+            .map(|index| {
+                let definite = descriptor
+                    .at_derivation_index(index)
+                    .map_err(|e| WalletError::Descriptor(e.to_string()))?;
+                Ok(definite.script_pubkey())
+            })
+            .collect()
+    }
+
+    /// Returns `count` external addresses starting at index `start`.
+    pub fn get_addresses(&self, start: u32, count: u32) -> Result<Vec<Address>> {
+        Ok(self
+            .address_iter(KeychainKind::External)?
+            .skip(start as usize)
+            .take(count as usize)
+            .map(|(_, address)| address)
+            .collect())
+    }
+
+    /// Esplora server used for balance, history and sync: `esplora_url` if
+    /// set, otherwise the public server for the wallet's network. Regtest has
+    /// no public server, so it needs `esplora_url`.
+    pub fn default_backend(&self) -> Result<Backend> {
+        if let Some(url) = &self.esplora_url {
+            return Ok(Backend::Esplora(url.clone()));
+        }
+        let client_url = match self.network {
+            Network::Bitcoin => "https://blockstream.info/api/",
+            Network::Testnet => "https://blockstream.info/testnet/api/",
+            Network::Signet => "https://mempool.space/signet/api/",
+            network => return Err(WalletError::UnsupportedNetwork(network.to_string())),
+        };
+        Ok(Backend::Esplora(client_url.to_string()))
+    }
+
+    fn esplora_client(&self) -> Result<esplora_client::BlockingClient> {
+        Ok(self.client_for(&self.default_backend()?))
+    }
+
+    fn client_for(&self, backend: &Backend) -> esplora_client::BlockingClient {
+        let Backend::Esplora(client_url) = backend;
+        log::debug!("Using Esplora backend {}", client_url);
+        Builder::new(client_url).timeout(self.request_timeout_secs).build_blocking()
+    }
+
+    pub fn sync_wallet(&self) -> Result<Wallet> {
+        self.sync_wallet_with_progress(|_| {})
+    }
+
+    /// Syncs the wallet, calling `progress` for every script checked during
+    /// the full scan. Starts from the cached changeset next to the wallet
+    /// file, if any, and updates the cache afterwards.
+    pub fn sync_wallet_with_progress(&self, progress: impl FnMut(ScanProgress) + Send + 'static) -> Result<Wallet> {
+        self.sync_from(false, STOP_GAP, progress, &AtomicBool::new(false))
+    }
+
+    /// Fast sync that stops after `QUICK_STOP_GAP` unused addresses instead
+    /// of `STOP_GAP`. Fine for a quick balance check on a lightly used
+    /// wallet, but can miss funds on addresses past a longer run of unused
+    /// ones; use `sync_wallet` when the full picture matters.
+    pub fn sync_wallet_quick(&self) -> Result<Wallet> {
+        self.sync_from(false, QUICK_STOP_GAP, |_| {}, &AtomicBool::new(false))
+    }
+
+    /// Syncs the wallet, returning `WalletError::Cancelled` once `cancel` is
+    /// set. The flag is checked between the scan steps, so another thread
+    /// (e.g. a Ctrl-C handler) can stop a long sync cleanly.
+    pub fn sync_wallet_cancellable(&self, cancel: &AtomicBool) -> Result<Wallet> {
+        self.sync_from(false, STOP_GAP, |_| {}, cancel)
+    }
+
+    /// Ignores the cached changeset, rescans from scratch and overwrites the
+    /// cache. Use after a reorg or if the cache looks wrong.
+    pub fn sync_wallet_full(&self) -> Result<Wallet> {
+        self.sync_from(true, STOP_GAP, |_| {}, &AtomicBool::new(false))
+    }
+
+    fn sync_from(
+        &self,
+        refresh: bool,
+        stop_gap: usize,
+        progress: impl FnMut(ScanProgress) + Send + 'static,
+        cancel: &AtomicBool,
+    ) -> Result<Wallet> {
+        let check_cancelled = || {
+            if cancel.load(Ordering::Relaxed) {
+                log::debug!("Sync cancelled");
+                return Err(WalletError::Cancelled);
+            }
+            Ok(())
+        };
+
+        log::debug!("Starting sync of {} wallet", self.network);
+        log::debug!("Using stop gap {}", stop_gap);
+        check_cancelled()?;
+        let client = self.esplora_client()?;
+        let (mut wallet, mut changeset) = self.starting_wallet(refresh, &client)?;
+
+        check_cancelled()?;
+        let full_scan = Self::full_scan_request(&wallet, progress);
+        let full_scan_res = client.full_scan(full_scan, stop_gap, PARALLEL_REQUESTS)?;
+        wallet.apply_update(full_scan_res)?;
+
+        check_cancelled()?;
+        let sync = wallet.start_sync_with_revealed_spks();
+        let sync_res = client.sync(sync, PARALLEL_REQUESTS)?;
+        wallet.apply_update(sync_res)?;
+
+        log::debug!("Synced to height {}", wallet.latest_checkpoint().height());
+        if let Some(staged) = wallet.take_staged() {
+            changeset.merge(staged);
+        }
+        self.store_changeset(&changeset)?;
+        Ok(wallet)
+    }
+
+    /// Syncs a fresh wallet against each backend and returns the balance each
+    /// one reports, so callers can flag a backend that disagrees. The
+    /// changeset cache is neither read nor written.
+    pub fn balance_cross_check(&self, backends: &[Backend]) -> Result<Vec<(Backend, u64)>> {
+        Self::cross_check_with(backends, |backend| {
+            let client = self.client_for(backend);
+            let mut wallet = self.create_wallet()?;
+            let full_scan = Self::full_scan_request(&wallet, |_| {});
+            wallet.apply_update(client.full_scan(full_scan, STOP_GAP, PARALLEL_REQUESTS)?)?;
+            let sync = wallet.start_sync_with_revealed_spks();
+            wallet.apply_update(client.sync(sync, PARALLEL_REQUESTS)?)?;
+            Ok(wallet.balance().total().to_sat())
+        })
+    }
+
+    fn cross_check_with(
+        backends: &[Backend],
+        mut balance_of: impl FnMut(&Backend) -> Result<u64>,
+    ) -> Result<Vec<(Backend, u64)>> {
+        backends
+            .iter()
+            .map(|backend| Ok((backend.clone(), balance_of(backend)?)))
+            .collect()
+    }
+
+    /// Changeset cache stored beside the wallet file, e.g.
+    /// `wallet.changeset.json`. Wallets without a path have no cache.
+    fn changeset_path(&self) -> Option<PathBuf> {
+        if self.wallet_path.as_os_str().is_empty() {
+            return None;
+        }
+        Some(self.wallet_path.with_extension("changeset.json"))
+    }
+
+    /// Returns the wallet to sync and the changeset it was loaded from.
+    /// With `refresh`, or when there is no cache, starts from a fresh wallet.
+    /// Cached blocks that `chain` no longer has are dropped first.
+    fn starting_wallet(&self, refresh: bool, chain: &impl BlockSource) -> Result<(Wallet, ChangeSet)> {
+        let cached = match self.load_changeset()? {
+            Some(mut changeset) if !refresh => {
+                Self::drop_reorged_blocks(&mut changeset, chain)?;
+                self.wallet_from_changeset(changeset.clone())?.map(|wallet| (wallet, changeset))
+            }
+            _ => None,
+        };
+        match cached {
+            Some(cached) => Ok(cached),
+            None => Ok((self.create_wallet()?, ChangeSet::default())),
+        }
+    }
+
+    /// Loads the cached changeset. A cache written for a different
+    /// descriptor (e.g. after `wallet.json` was edited), or in the untagged
+    /// format, is discarded so the next sync does a full scan.
+    fn load_changeset(&self) -> Result<Option<ChangeSet>> {
+        let path = match self.changeset_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        match serde_json::from_str::<CachedChangeset>(&fs::read_to_string(path)?) {
+            Ok(cached) if cached.descriptor_hash == self.descriptor_hash()? => Ok(Some(cached.changeset)),
+            _ => {
+                log::debug!("Cached changeset does not match the descriptor; discarding it");
+                Ok(None)
+            }
+        }
+    }
+
+    fn descriptor_hash(&self) -> Result<String> {
+        let mut descriptor = self.parsed_descriptor()?.to_string();
+        if let Some(change) = self.parsed_change_descriptor()? {
+            descriptor.push_str(&change.to_string());
+        }
+        Ok(sha256::Hash::hash(descriptor.as_bytes()).to_string())
+    }
+
+    /// Merges a changeset synced on another machine into the cached state:
+    /// transactions are unioned and the chain keeps the higher tip.
+    pub fn merge_changeset(&mut self, other: &ChangeSet) -> Result<()> {
+        if self.changeset_path().is_none() {
+            return Err(WalletError::NoWalletPath);
+        }
+        if let Some(descriptor) = &other.descriptor {
+            if descriptor != self.parsed_descriptor()? {
+                return Err(WalletError::Cache("Changeset belongs to a different descriptor".to_string()));
+            }
+        }
+        if other.network.is_some_and(|network| network != self.network) {
+            return Err(WalletError::NetworkMismatch { expected: self.network });
+        }
+
+        let mut changeset = self.load_changeset()?.unwrap_or_default();
+        changeset.merge(other.clone());
+        // Make sure the merged state still loads before replacing the cache
+        self.wallet_from_changeset(changeset.clone())?;
+        self.store_changeset(&changeset)
+    }
+
+    fn wallet_from_changeset(&self, changeset: ChangeSet) -> Result<Option<Wallet>> {
+        Ok(Wallet::load()
+            .descriptor(KeychainKind::External, Some(self.parsed_descriptor()?.clone()))
+            .descriptor(KeychainKind::Internal, self.parsed_change_descriptor()?)
+            .check_network(self.to_bdk_network())
+            .load_wallet_no_persist(changeset)?)
+    }
+
+    /// Removes cached blocks, from the tip down, whose hash no longer
+    /// matches `chain`, stopping at the first block that still matches. A
+    /// height the backend no longer knows is treated as stale. Transactions
+    /// anchored only in those blocks stop counting as confirmed, so a reorg
+    /// cannot inflate the balance.
+    fn drop_reorged_blocks(changeset: &mut ChangeSet, chain: &impl BlockSource) -> Result<()> {
+        let mut stale = Vec::new();
+        for (&height, hash) in changeset.local_chain.blocks.iter().rev() {
+            // Genesis never reorgs
+            if height == 0 {
+                break;
+            }
+            let Some(hash) = hash else {
+                continue;
+            };
+            if chain.block_hash(height)? == Some(*hash) {
+                break;
+            }
+            stale.push(height);
+        }
+        if stale.is_empty() {
+            return Ok(());
+        }
+        log::debug!("Dropping {} reorged blocks from the cache", stale.len());
+        for height in &stale {
+            changeset.local_chain.blocks.remove(height);
+        }
+
+        // Forget confirmations in the dropped blocks. A transaction left with
+        // no anchor that was never seen in the mempool is gone from the
+        // chain, so drop it too rather than keep counting it
+        let tx_graph = &mut changeset.tx_graph;
+        let mut orphaned = Vec::new();
+        tx_graph.anchors.retain(|(anchor, txid)| {
+            let keep = !stale.contains(&anchor.block_id.height);
+            if !keep {
+                orphaned.push(*txid);
+            }
+            keep
+        });
+        let gone: Vec<Txid> = orphaned
+            .into_iter()
+            .filter(|txid| {
+                !tx_graph.anchors.iter().any(|(_, anchored)| anchored == txid) && !tx_graph.last_seen.contains_key(txid)
+            })
+            .collect();
+        tx_graph.txs.retain(|tx| !gone.contains(&tx.compute_txid()));
+        Ok(())
+    }
+
+    fn store_changeset(&self, changeset: &ChangeSet) -> Result<()> {
+        if let Some(path) = self.changeset_path() {
+            let cached = CachedChangeset { descriptor_hash: self.descriptor_hash()?, changeset: changeset.clone() };
+            fs::write(path, serde_json::to_string(&cached)?)?;
+        }
+        Ok(())
+    }
+
+    fn full_scan_request(
+        wallet: &Wallet,
+        mut progress: impl FnMut(ScanProgress) + Send + 'static,
+    ) -> FullScanRequest<KeychainKind> {
+        let mut scripts_processed = 0;
+        wallet
+            .start_full_scan()
+            .inspect(move |keychain, index, _| {
+                scripts_processed += 1;
+                log::trace!("Scanning {:?} index {}", keychain, index);
+                progress(ScanProgress { keychain, index, scripts_processed });
+            })
+            .build()
+    }
+
+    /// Syncs once and summarizes balance, UTXOs and history.
+    pub fn status(&self) -> Result<WalletStatus> {
+        let wallet = self.sync_wallet()?;
+        self.status_of(&wallet)
+    }
+
+    /// Summarizes an already synced wallet, e.g. one from `sync_wallet_full`.
+    pub fn status_of(&self, wallet: &Wallet) -> Result<WalletStatus> {
+        let balance = wallet.balance();
+        Ok(WalletStatus {
+            network: self.network,
+            policy: self.policy()?,
+            confirmed_sats: balance.confirmed.to_sat(),
+            unconfirmed_sats: (balance.trusted_pending + balance.untrusted_pending).to_sat(),
+            utxo_count: wallet.list_unspent().count(),
+            tx_count: wallet.transactions().count(),
+            sync_height: wallet.latest_checkpoint().height(),
+        })
+    }
+
+    pub fn get_balance(&self) -> Result<u64> {
+        Ok(self.sync_wallet()?.balance().total().to_sat())
+    }
+
+    pub fn list_transactions(&self) -> Result<()> {
+        // Sync the wallet to get the latest transaction data. This can fail.
+        let synced_wallet = self.sync_wallet()?;
+
+        let transactions: Vec<WalletTx> = synced_wallet.transactions().collect();
+
+        log::info!("Found {} transactions", transactions.len());
+        for wallet_tx in transactions {
+            log::info!("{} TXID: {} at {}", wallet_tx.chain_position.is_confirmed(), wallet_tx.tx_node.txid, wallet_tx.tx_node.lock_time);
+        }
+        Ok(())
+    }
+
+    /// Returns every wallet transaction, newest first.
+    pub fn get_transactions(&self) -> Result<Vec<TxSummary>> {
+        self.get_transactions_paged(0, usize::MAX)
+    }
+
+    /// Returns up to `limit` wallet transactions after skipping `offset`,
+    /// newest first. Unconfirmed transactions sort before confirmed ones.
+    pub fn get_transactions_paged(&self, offset: usize, limit: usize) -> Result<Vec<TxSummary>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::transactions_page(&wallet, offset, limit))
+    }
+
+    fn transactions_page(wallet: &Wallet, offset: usize, limit: usize) -> Vec<TxSummary> {
+        let mut txs: Vec<WalletTx> = wallet.transactions().collect();
+        txs.sort_by_key(|tx| {
+            let recency = match tx.chain_position {
+                ChainPosition::Confirmed { anchor, .. } => (false, anchor.block_id.height as u64),
+                ChainPosition::Unconfirmed { last_seen, .. } => (true, last_seen.unwrap_or(0)),
+            };
+            std::cmp::Reverse((recency, tx.tx_node.txid))
+        });
+        txs.iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|tx| Self::tx_summary(wallet, tx.tx_node.txid, false))
+            .collect()
+    }
+
+    /// Looks up one transaction in the synced wallet. Returns `None` if the
+    /// transaction does not involve this wallet.
+    pub fn get_transaction(&self, txid: Txid, include_hex: bool) -> Result<Option<TxSummary>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::tx_summary(&wallet, txid, include_hex))
+    }
+
+    fn tx_summary(wallet: &Wallet, txid: Txid, include_hex: bool) -> Option<TxSummary> {
+        let wallet_tx = wallet.get_tx(txid)?;
+        let tx = &wallet_tx.tx_node.tx;
+        let (sent, received) = wallet.sent_and_received(tx);
+        let confirmation_height = match wallet_tx.chain_position {
+            ChainPosition::Confirmed { anchor, .. } => Some(anchor.block_id.height),
+            ChainPosition::Unconfirmed { .. } => None,
+        };
+
+        Some(TxSummary {
+            txid,
+            received: received.to_sat(),
+            sent: sent.to_sat(),
+            fee: wallet.calculate_fee(tx).ok().map(|fee| fee.to_sat()),
+            confirmation_height,
+            raw_hex: include_hex.then(|| serialize_hex(tx.as_ref())),
+        })
+    }
+
+    /// Reports how many signatures have been collected on `psbt`. Inputs
+    /// with mixed progress are summarized by the least-signed input;
+    /// already finalized inputs count as fully signed. Only signatures by
+    /// the input's cosigner keys count, up to the threshold.
+    pub fn signing_progress(&self, psbt: &Psbt) -> Result<SigningProgress> {
+        let required = self.threshold()?;
+        let collected = psbt
+            .inputs
+            .iter()
+            .map(|input| {
+                if input.final_script_witness.is_some() || input.final_script_sig.is_some() {
+                    required
+                } else {
+                    let signed = input.partial_sigs.keys().filter(|key| Self::is_cosigner_key(input, key)).count();
+                    signed.min(required)
+                }
+            })
+            .min()
+            .unwrap_or(0);
+
+        Ok(SigningProgress {
+            collected,
+            required,
+            can_finalize: !psbt.inputs.is_empty() && collected >= required,
+        })
+    }
+
+    /// Whether `key` is one of the keys `input` is locked to: listed in its
+    /// BIP32 derivations or pushed by its witness script.
+    fn is_cosigner_key(input: &bitcoin::psbt::Input, key: &bitcoin::PublicKey) -> bool {
+        let key_bytes = key.to_bytes();
+        input.bip32_derivation.contains_key(&key.inner)
+            || input.witness_script.as_ref().is_some_and(|script| {
+                script.instructions().any(|ins| {
+                    matches!(ins, Ok(bitcoin::script::Instruction::PushBytes(bytes)) if bytes.as_bytes() == key_bytes.as_slice())
+                })
+            })
+    }
+
+    /// Checks the outputs of `tx` before signing: flags scripts that aren't
+    /// standard payment scripts (P2PK, P2PKH, P2SH, P2WPKH, P2WSH, P2TR) and
+    /// OP_RETURN outputs with a nonzero value.
+    pub fn verify_transaction(&self, tx: &Transaction) -> Result<TxVerification> {
+        let mut verification = TxVerification::default();
+        for (index, output) in tx.output.iter().enumerate() {
+            let script = &output.script_pubkey;
+            if script.is_op_return() {
+                if output.value > Amount::ZERO {
+                    log::warn!("Output {} burns {} in an OP_RETURN", index, output.value);
+                    verification.burned_outputs.push((index, output.value));
+                }
+            } else if !(script.is_p2pk()
+                || script.is_p2pkh()
+                || script.is_p2sh()
+                || script.is_p2wpkh()
+                || script.is_p2wsh()
+                || script.is_p2tr())
+            {
+                log::warn!("Output {} has a non-standard script {}", index, script);
+                verification.nonstandard_outputs.push(index);
+            }
+        }
+        Ok(verification)
+    }
+
+    /// Signs `psbt` with `xpriv`, which must be one of the wallet's cosigner
+    /// keys (or the master key of one with an origin). Works offline: only
+    /// the descriptor and the PSBT are used. Returns whether any signature
+    /// was added.
+    pub fn sign_psbt(&self, psbt: &mut Psbt, xpriv: &Xpriv) -> Result<bool> {
+        if !self.keys_match_descriptor(&[*xpriv])?[0] {
+            return Err(WalletError::Key(format!(
+                "Key with fingerprint {} is not a cosigner of this wallet",
+                xpriv.fingerprint(&self.secp)
+            )));
+        }
+        Self::sign_with(&self.secp, psbt, &[*xpriv])
+    }
+
+    /// Finalizes every input that has enough signatures, without syncing.
+    /// Returns whether all inputs were finalized, after which
+    /// `Psbt::extract_tx` gives the broadcastable transaction.
+    pub fn finalize_psbt(&self, psbt: &mut Psbt) -> Result<bool> {
+        let wallet = self.create_wallet()?;
+        wallet
+            .finalize_psbt(psbt, SignOptions::default())
+            .map_err(|e| WalletError::Transaction(format!("Failed to finalize PSBT: {}", e)))
+    }
+
+    /// Txid the finalized `psbt` will have once broadcast. Errors if any
+    /// input is not finalized or the transaction can't be extracted.
+    pub fn tx_id_of(psbt: &Psbt) -> Result<Txid> {
+        let finalized = psbt
+            .inputs
+            .iter()
+            .all(|input| input.final_script_witness.is_some() || input.final_script_sig.is_some());
+        if psbt.inputs.is_empty() || !finalized {
+            return Err(WalletError::Transaction("PSBT is not finalized".to_string()));
+        }
+        let tx = psbt
+            .clone()
+            .extract_tx()
+            .map_err(|e| WalletError::Transaction(format!("Failed to extract transaction: {}", e)))?;
+        Ok(tx.compute_txid())
+    }
+
+    /// Signs `psbt` with the cosigner key derived at `path` from a BIP39
+    /// mnemonic and optional passphrase, using the standard BIP39 seed. No
+    /// network calls. Returns whether any signature was added.
+    pub fn sign_psbt_with_mnemonic(
+        &self,
+        psbt: &mut Psbt,
+        mnemonic: &str,
+        passphrase: Option<&str>,
+        path: &DerivationPath,
+    ) -> Result<bool> {
+        let mnemonic = Mnemonic::parse(mnemonic).map_err(|e| WalletError::Key(format!("Invalid mnemonic: {}", e)))?;
+        let seed = mnemonic.to_seed(passphrase.unwrap_or(""));
+        let master = Xpriv::new_master(self.network, &seed)?;
+        let account = master.derive_priv(&self.secp, path)?;
+
+        // Keys with an origin are matched through the master key, bare
+        // account xpubs through the derived one
+        if !self.keys_match_descriptor(&[master, account])?.contains(&true) {
+            return Err(WalletError::Key(format!(
+                "Key at {} from mnemonic with fingerprint {} is not a cosigner of this wallet; check the path and passphrase",
+                path,
+                master.fingerprint(&self.secp)
+            )));
+        }
+        Self::sign_with(&self.secp, psbt, &[master, account])
+    }
+
+    /// Adds signatures from each of `xprivs` for every PSBT input whose
+    /// BIP32 derivations they can produce. Returns whether any was added.
+    fn sign_with(secp: &Secp256k1<All>, psbt: &mut Psbt, xprivs: &[Xpriv]) -> Result<bool> {
+        let count = |psbt: &Psbt| psbt.inputs.iter().map(|input| input.partial_sigs.len()).sum::<usize>();
+        let before = count(psbt);
+        for xpriv in xprivs {
+            psbt.sign(xpriv, secp)
+                .map_err(|(_, errors)| WalletError::Transaction(format!("Signing failed: {:?}", errors)))?;
+        }
+        Ok(count(psbt) > before)
+    }
+
+    /// Fetches a transaction from the backend by txid, whether or not it
+    /// touches this wallet, e.g. to inspect or re-broadcast it.
+    pub fn fetch_raw_tx(&self, txid: Txid) -> Result<Transaction> {
+        let client = self.esplora_client()?;
+        Self::fetch_raw_tx_from(&client, txid)
+    }
+
+    fn fetch_raw_tx_from(source: &impl TxSource, txid: Txid) -> Result<Transaction> {
+        source.raw_tx(txid)?.ok_or(WalletError::TxNotFound(txid))
+    }
+
+    /// Returns the beacon addresses that have received funds, together with
+    /// the total amount received by each (confirmed and unconfirmed).
+    pub fn find_beacon_activity(&self, beacon_addrs: &[Address]) -> Result<Vec<(Address, u64)>> {
+        let client = self.esplora_client()?;
+        Self::collect_beacon_activity(&client, beacon_addrs)
+    }
+
+    fn collect_beacon_activity(source: &impl AddressActivity, beacon_addrs: &[Address]) -> Result<Vec<(Address, u64)>> {
+        let mut activity = Vec::new();
+        for address in beacon_addrs {
+            let (confirmed, unconfirmed) = source.received_sats(address)?;
+            let received = confirmed + unconfirmed;
+            if received > 0 {
+                activity.push((address.clone(), received));
+            }
+        }
+        Ok(activity)
+    }
+
+    /// Returns `(confirmed_received, unconfirmed_received)` for one of the
+    /// wallet's own addresses without syncing the whole wallet.
+    pub fn balance_of_address(&self, address: &Address) -> Result<(u64, u64)> {
+        let client = self.esplora_client()?;
+        self.address_received(&client, address)
+    }
+
+    fn address_received(&self, source: &impl AddressActivity, address: &Address) -> Result<(u64, u64)> {
+        if !self.create_wallet()?.is_mine(address.script_pubkey()) {
+            return Err(WalletError::NotMine(address.to_string()));
+        }
+        source.received_sats(address)
+    }
+
+    /// Checks up to `GAP_PROBE_LIMIT` external addresses past the scanned
+    /// range for history a full scan would miss. Returns the highest used
+    /// index found there, if any.
+    pub fn detect_used_beyond_gap(&self) -> Result<Option<u32>> {
+        let wallet = self.sync_wallet()?;
+        let scanned_to = wallet
+            .derivation_index(KeychainKind::External)
+            .map_or(0, |index| index + 1)
+            + STOP_GAP as u32;
+        let client = self.esplora_client()?;
+        self.highest_used_from(&client, scanned_to)
+    }
+
+    fn highest_used_from(&self, source: &impl AddressActivity, start: u32) -> Result<Option<u32>> {
+        let mut highest = None;
+        for (index, address) in self.address_iter(KeychainKind::External)?.skip(start as usize).take(GAP_PROBE_LIMIT) {
+            let (confirmed, unconfirmed) = source.received_sats(&address)?;
+            if confirmed + unconfirmed > 0 {
+                highest = Some(index);
+            }
+        }
+        Ok(highest)
+    }
+
+    /// Returns every external address that has received funds, as
+    /// `(index, address, total_received_sats)` in index order.
+    pub fn used_addresses(&self) -> Result<Vec<(u32, Address, u64)>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::used_addresses_in(&wallet))
+    }
+
+    fn used_addresses_in(wallet: &Wallet) -> Vec<(u32, Address, u64)> {
+        let mut received: BTreeMap<u32, u64> = BTreeMap::new();
+        for wallet_tx in wallet.transactions() {
+            for output in &wallet_tx.tx_node.tx.output {
+                if let Some((KeychainKind::External, index)) = wallet.derivation_of_spk(output.script_pubkey.clone()) {
+                    *received.entry(index).or_default() += output.value.to_sat();
+                }
+            }
+        }
+        received
+            .into_iter()
+            .filter(|(_, amount)| *amount > 0)
+            .map(|(index, amount)| (index, wallet.peek_address(KeychainKind::External, index).address, amount))
+            .collect()
+    }
+
+    /// Lists every address of `keychain` from index 0 through `STOP_GAP`
+    /// past the highest revealed one, with whether each has been used.
+    pub fn keychain_report(&self, keychain: KeychainKind) -> Result<Vec<AddressStatus>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::keychain_report_in(&wallet, keychain))
+    }
+
+    fn keychain_report_in(wallet: &Wallet, keychain: KeychainKind) -> Vec<AddressStatus> {
+        let mut received: BTreeMap<u32, u64> = BTreeMap::new();
+        for wallet_tx in wallet.transactions() {
+            for output in &wallet_tx.tx_node.tx.output {
+                if let Some((output_keychain, index)) = wallet.derivation_of_spk(output.script_pubkey.clone()) {
+                    if output_keychain == keychain {
+                        *received.entry(index).or_default() += output.value.to_sat();
+                    }
+                }
+            }
+        }
+        let highest_revealed = wallet.derivation_index(keychain);
+        let end = highest_revealed.map_or(0, |index| index + 1) + STOP_GAP as u32;
+        (0..end)
+            .map(|index| AddressStatus {
+                index,
+                address: wallet.peek_address(keychain, index).address,
+                revealed: highest_revealed.is_some_and(|highest| index <= highest),
+                used: received.contains_key(&index),
+                received_sats: received.get(&index).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// Returns receive addresses that were paid by more than one
+    /// transaction, with the number of transactions, in index order.
+    pub fn reused_addresses(&self) -> Result<Vec<(Address, usize)>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::reused_addresses_in(&wallet))
+    }
+
+    fn reused_addresses_in(wallet: &Wallet) -> Vec<(Address, usize)> {
+        let mut tx_counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for wallet_tx in wallet.transactions() {
+            let mut indexes: Vec<u32> = wallet_tx
+                .tx_node
+                .tx
+                .output
+                .iter()
+                .filter_map(|output| match wallet.derivation_of_spk(output.script_pubkey.clone()) {
+                    Some((KeychainKind::External, index)) => Some(index),
+                    _ => None,
+                })
+                .collect();
+            // Several outputs to one address in the same transaction count once
+            indexes.sort_unstable();
+            indexes.dedup();
+            for index in indexes {
+                *tx_counts.entry(index).or_default() += 1;
+            }
+        }
+        tx_counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(index, count)| (wallet.peek_address(KeychainKind::External, index).address, count))
+            .collect()
+    }
+
+    /// Fee rate from the backend's estimate for confirmation within
+    /// `target_blocks`, using the estimate for the nearest target at or below
+    /// it. Never below `fee_limits.min_fee_rate`, which is also the fallback
+    /// when the backend returns no estimates.
+    pub fn estimate_fee_rate(&self, target_blocks: u16) -> Result<FeeRate> {
+        let client = self.esplora_client()?;
+        Self::fee_rate_from(&client, target_blocks, &self.fee_limits)
+    }
+
+    fn fee_rate_from(source: &impl FeeSource, target_blocks: u16, fee_limits: &FeeLimits) -> Result<FeeRate> {
+        let estimates = source.fee_estimates()?;
+        let sat_per_vb = estimates
+            .iter()
+            .filter(|(target, _)| **target <= target_blocks)
+            .max_by_key(|(target, _)| **target)
+            .map(|(_, rate)| *rate);
+        let fee_rate = match sat_per_vb {
+            // 1 sat/vB is 250 sat/kwu; round up so the estimate isn't undershot
+            Some(rate) if rate.is_finite() && rate > 0.0 => {
+                FeeRate::from_sat_per_kwu((rate * 250.0).ceil() as u64).max(fee_limits.min_fee_rate)
+            }
+            _ => {
+                log::debug!("No fee estimate for {} blocks; using {}", target_blocks, fee_limits.min_fee_rate);
+                fee_limits.min_fee_rate
+            }
+        };
+        fee_limits.check_fee_rate(fee_rate)?;
+        Ok(fee_rate)
+    }
+
+    /// Lists every unspent output the wallet controls.
+    pub fn list_utxos(&self) -> Result<Vec<Utxo>> {
+        let wallet = self.sync_wallet()?;
+        Ok(Self::list_utxos_in(&wallet))
+    }
+
+    fn list_utxos_in(wallet: &Wallet) -> Vec<Utxo> {
+        wallet
+            .list_unspent()
+            .map(|utxo| Utxo {
+                outpoint: utxo.outpoint,
+                value_sats: utxo.txout.value.to_sat(),
+                keychain: utxo.keychain,
+                derivation_index: utxo.derivation_index,
+                confirmation_height: match utxo.chain_position {
+                    ChainPosition::Confirmed { anchor, .. } => Some(anchor.block_id.height),
+                    ChainPosition::Unconfirmed { .. } => None,
+                },
+            })
+            .collect()
+    }
+
+    /// UTXOs that a transaction mined in the block after `current_height`
+    /// could spend: immature coinbase outputs are left out, as are outputs
+    /// whose descriptor has no spending path whose CSV/CLTV locks have passed.
+    pub fn spendable_utxos(&self, current_height: u32) -> Result<Vec<LocalOutput>> {
+        let wallet = self.sync_wallet()?;
+        self.spendable_utxos_in(&wallet, current_height)
+    }
+
+    fn spendable_utxos_in(&self, wallet: &Wallet, current_height: u32) -> Result<Vec<LocalOutput>> {
+        let policy = self.parsed_descriptor()?.lift()?;
+        let next_height = current_height + 1;
+        let lock_time = absolute::LockTime::from_consensus(current_height);
+
+        Ok(wallet
+            .list_unspent()
+            .filter(|utxo| {
+                let confirmed_at = match &utxo.chain_position {
+                    ChainPosition::Confirmed { anchor, .. } => Some(anchor.block_id.height),
+                    ChainPosition::Unconfirmed { .. } => None,
+                };
+                let confirmations = confirmed_at.map_or(0, |height| next_height.saturating_sub(height));
+
+                let coinbase = wallet
+                    .get_tx(utxo.outpoint.txid)
+                    .is_some_and(|tx| tx.tx_node.tx.is_coinbase());
+                if coinbase && confirmations < COINBASE_MATURITY {
+                    return false;
+                }
+
+                let age = relative::LockTime::from_height(confirmations.min(u16::MAX as u32) as u16);
+                policy.clone().at_age(age).at_lock_time(lock_time).normalized() != SemanticPolicy::Unsatisfiable
+            })
+            .collect())
+    }
+
+    /// Largest amount that can be sent to `to` by draining every UTXO at
+    /// `fee_rate`. Returns zero when the fee would consume the whole balance.
+    /// Rates above `fee_limits.max_fee_rate` are rejected.
+    pub fn max_sendable(&self, to: &Address, fee_rate: FeeRate) -> Result<Amount> {
+        let mut wallet = self.sync_wallet()?;
+        Self::max_sendable_in(&mut wallet, to, fee_rate, &self.fee_limits)
+    }
+
+    fn max_sendable_in(wallet: &mut Wallet, to: &Address, fee_rate: FeeRate, fee_limits: &FeeLimits) -> Result<Amount> {
+        fee_limits.check_fee_rate(fee_rate)?;
+
+        let mut tx_builder = wallet.build_tx();
+        tx_builder.drain_wallet();
+        tx_builder.drain_to(to.script_pubkey());
+        tx_builder.fee_rate(fee_rate);
+
+        match tx_builder.finish() {
+            Ok(psbt) => Ok(psbt.unsigned_tx.output.iter().map(|out| out.value).sum()),
+            Err(CreateTxError::CoinSelection(_))
+            | Err(CreateTxError::OutputBelowDustLimit(_))
+            | Err(CreateTxError::NoUtxosSelected) => Ok(Amount::ZERO),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Picks the change address according to `policy`, revealing it so the
+    /// wallet tracks it.
+    fn change_address(wallet: &mut Wallet, policy: ChangePolicy) -> Address {
+        let next = wallet.next_unused_address(KeychainKind::Internal);
+        match policy {
+            ChangePolicy::Sequential => next.address,
+            ChangePolicy::Random => {
+                let index = next.index + rand::thread_rng().gen_range(0..STOP_GAP as u32);
+                wallet
+                    .reveal_addresses_to(KeychainKind::Internal, index)
+                    .last()
+                    .map_or(next.address, |info| info.address)
+            }
+        }
+    }
+
+    /// Syncs and hands `f` a transaction builder whose change already goes
+    /// to an address picked by `change_policy`, then returns the finished
+    /// PSBT. `f` adds recipients, OP_RETURNs, RBF, manual UTXOs and so on;
+    /// `fee_limits` are not applied to what it sets.
+    pub fn build_psbt<F>(&self, f: F) -> Result<Psbt>
+    where
+        F: FnOnce(&mut TxBuilder<'_, DefaultCoinSelectionAlgorithm>),
+    {
+        let mut wallet = self.sync_wallet()?;
+        Self::build_psbt_in(&mut wallet, self.change_policy, self.locktime, f)
+    }
+
+    fn build_psbt_in<F>(
+        wallet: &mut Wallet,
+        change_policy: ChangePolicy,
+        locktime: Option<absolute::LockTime>,
+        f: F,
+    ) -> Result<Psbt>
+    where
+        F: FnOnce(&mut TxBuilder<'_, DefaultCoinSelectionAlgorithm>),
+    {
+        let change_address = Self::change_address(wallet, change_policy);
+        let mut tx_builder = wallet.build_tx();
+        tx_builder.drain_to(change_address.script_pubkey());
+        // Without an explicit locktime bdk uses the tip height, like Core
+        if let Some(locktime) = locktime {
+            tx_builder.nlocktime(locktime);
+        }
+        f(&mut tx_builder);
+        Ok(tx_builder.finish()?)
+    }
+
+    /// Builds a PSBT paying `recipients` with exactly `fee` in fees. The fee
+    /// must cover the minimum relay fee for the signed transaction size and
+    /// must be less than the amount being sent, and within `fee_limits`.
+    pub fn create_transaction_abs_fee(&self, recipients: &[(Address, Amount)], fee: Amount) -> Result<Psbt> {
+        let mut wallet = self.sync_wallet()?;
+        Self::build_abs_fee_psbt(&mut wallet, recipients, fee, self.change_policy, self.locktime, &self.fee_limits)
+    }
+
+    fn build_abs_fee_psbt(
+        wallet: &mut Wallet,
+        recipients: &[(Address, Amount)],
+        fee: Amount,
+        change_policy: ChangePolicy,
+        locktime: Option<absolute::LockTime>,
+        fee_limits: &FeeLimits,
+    ) -> Result<Psbt> {
+        for (address, amount) in recipients {
+            check_not_dust(&address.script_pubkey(), *amount)?;
+        }
+        let total_sent: Amount = recipients.iter().map(|(_, amount)| *amount).sum();
+        if fee >= total_sent {
+            return Err(WalletError::InvalidFee(format!("fee {} is not below the amount sent {}", fee, total_sent)));
+        }
+        fee_limits.check_absolute_fee(fee, total_sent)?;
+
+        let psbt = Self::build_psbt_in(wallet, change_policy, locktime, |tx_builder| {
+            for (address, amount) in recipients {
+                tx_builder.add_recipient(address.script_pubkey(), *amount);
+            }
+            tx_builder.fee_absolute(fee);
+        })?;
+
+        // The unsigned transaction has no witnesses, so add the worst-case
+        // satisfaction weight of each input
+        let satisfaction_weight = wallet.public_descriptor(KeychainKind::External).max_weight_to_satisfy()?;
+        let signed_weight = Weight::from_wu(
+            psbt.unsigned_tx.weight().to_wu() + psbt.inputs.len() as u64 * satisfaction_weight.to_wu(),
+        );
+        let min_fee = FeeRate::BROADCAST_MIN.fee_wu(signed_weight).unwrap_or(Amount::MAX_MONEY);
+        if fee < min_fee {
+            return Err(WalletError::InvalidFee(format!("fee {} is below the minimum relay fee {}", fee, min_fee)));
+        }
+        Ok(psbt)
+    }
+
+    /// Builds a PSBT paying `recipients` (address, sats) from exactly the
+    /// given `utxos`, with change back to the wallet, at the fee rate
+    /// estimated for `DEFAULT_CONFIRMATION_TARGET` blocks. Fails with
+    /// `InsufficientFunds` when the UTXOs can't cover the outputs and fee.
+    pub fn create_transaction_with_utxos(&self, recipients: Vec<(Address, u64)>, utxos: Vec<OutPoint>) -> Result<Psbt> {
+        let fee_rate = self.estimate_fee_rate(DEFAULT_CONFIRMATION_TARGET)?;
+        let mut wallet = self.sync_wallet()?;
+        Self::build_manual_psbt(&mut wallet, &recipients, &utxos, fee_rate, self.change_policy, self.locktime)
+    }
+
+    fn build_manual_psbt(
+        wallet: &mut Wallet,
+        recipients: &[(Address, u64)],
+        utxos: &[OutPoint],
+        fee_rate: FeeRate,
+        change_policy: ChangePolicy,
+        locktime: Option<absolute::LockTime>,
+    ) -> Result<Psbt> {
+        if utxos.is_empty() {
+            return Err(WalletError::Transaction("No UTXOs selected".to_string()));
+        }
+        let mut available = Amount::ZERO;
+        for outpoint in utxos {
+            let utxo = wallet
+                .get_utxo(*outpoint)
+                .ok_or_else(|| WalletError::Transaction(format!("{} is not an unspent output of this wallet", outpoint)))?;
+            available += utxo.txout.value;
+        }
+        let mut needed = Amount::ZERO;
+        for (address, sats) in recipients {
+            let amount = Amount::from_sat(*sats);
+            check_not_dust(&address.script_pubkey(), amount)?;
+            needed += amount;
+        }
+        // bdk reports the same once the fee is included; this catches the
+        // plain case before building anything
+        if needed > available {
+            return Err(WalletError::InsufficientFunds { needed, available });
+        }
+
+        Self::build_psbt_in(wallet, change_policy, locktime, |tx_builder| {
+            for (address, sats) in recipients {
+                tx_builder.add_recipient(address.script_pubkey(), Amount::from_sat(*sats));
+            }
+            // Every outpoint was looked up above, so this can't fail
+            let _ = tx_builder.add_utxos(utxos);
+            tx_builder.manually_selected_only();
+            tx_builder.fee_rate(fee_rate);
+        })
+    }
+
+    /// Builds a beacon transaction paying `send_address` and embedding
+    /// `payload` in an OP_RETURN output. The payload must be at most
+    /// `MAX_OP_RETURN_SIZE` bytes to stay standard.
+    /// The fee rate is estimated for confirmation within
+    /// `DEFAULT_CONFIRMATION_TARGET` blocks.
+    pub fn create_opreturn_transaction(&self, send_address: Address, payload: &[u8]) -> Result<Psbt> {
+        let fee_rate = self.estimate_fee_rate(DEFAULT_CONFIRMATION_TARGET)?;
+        let mut wallet = self.sync_wallet()?;
+        Self::build_opreturn_psbt(&mut wallet, send_address, payload, fee_rate, self.change_policy, self.locktime)
+    }
+
+    fn build_opreturn_psbt(
+        wallet: &mut Wallet,
+        send_address: Address,
+        payload: &[u8],
+        fee_rate: FeeRate,
+        change_policy: ChangePolicy,
+        locktime: Option<absolute::LockTime>,
+    ) -> Result<Psbt> {
+        if payload.len() > MAX_OP_RETURN_SIZE {
+            return Err(WalletError::OpReturnTooLarge { size: payload.len(), max: MAX_OP_RETURN_SIZE });
+        }
+        let data = PushBytesBuf::try_from(payload.to_vec())
+            .map_err(|_| WalletError::OpReturnTooLarge { size: payload.len(), max: MAX_OP_RETURN_SIZE })?;
+
+        let beacon_value = Amount::from_sat(BEACON_OUTPUT_SATS);
+        check_not_dust(&send_address.script_pubkey(), beacon_value)?;
+
+        Self::build_psbt_in(wallet, change_policy, locktime, |tx_builder| {
+            tx_builder.add_recipient(send_address.script_pubkey(), beacon_value);
+            tx_builder.add_data(&data);
+            tx_builder.fee_rate(fee_rate);
+        })
+    }
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Derives the address at `index` from any descriptor string without
+/// building a wallet. Multipath descriptors use their first (external) branch.
+pub fn address_from_descriptor(descriptor: &str, index: u32, network: Network) -> Result<Address> {
+    let desc = Descriptor::<DescriptorPublicKey>::from_str(descriptor)?;
+    let desc = if desc.is_multipath() {
+        desc.into_single_descriptors()?.remove(0)
+    } else {
+        desc
+    };
+    if !desc.has_wildcard() && index > 0 {
+        return Err(WalletError::Descriptor(format!(
+            "descriptor has no wildcard, so index 0 is its only address (got {})",
+            index
+        )));
+    }
+    let definite = desc
+        .at_derivation_index(index)
+        .map_err(|e| WalletError::Descriptor(e.to_string()))?;
+    Ok(definite.address(network)?)
+}
+
+/// Source of per-address funding information.
+trait AddressActivity {
+    /// Total sats ever received by `address`, as (confirmed, unconfirmed).
+    fn received_sats(&self, address: &Address) -> Result<(u64, u64)>;
+}
+
+impl AddressActivity for esplora_client::BlockingClient {
+    fn received_sats(&self, address: &Address) -> Result<(u64, u64)> {
+        let stats = self.get_address_stats(address)?;
+        Ok((stats.chain_stats.funded_txo_sum, stats.mempool_stats.funded_txo_sum))
+    }
+}
+
+/// Source of block hashes on the backend's best chain.
+trait BlockSource {
+    /// Hash of the block at `height`, or `None` if the chain is shorter.
+    fn block_hash(&self, height: u32) -> Result<Option<BlockHash>>;
+}
+
+impl BlockSource for esplora_client::BlockingClient {
+    fn block_hash(&self, height: u32) -> Result<Option<BlockHash>> {
+        match self.get_block_hash(height) {
+            Ok(hash) => Ok(Some(hash)),
+            Err(esplora_client::Error::HttpResponse { status: 404, .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Source of fee rate estimates, in sat/vB by confirmation target in blocks.
+trait FeeSource {
+    fn fee_estimates(&self) -> Result<HashMap<u16, f64>>;
+}
+
+impl FeeSource for esplora_client::BlockingClient {
+    fn fee_estimates(&self) -> Result<HashMap<u16, f64>> {
+        Ok(self.get_fee_estimates()?)
+    }
+}
+
+/// Source of raw transactions by txid.
+trait TxSource {
+    fn raw_tx(&self, txid: Txid) -> Result<Option<Transaction>>;
+}
+
+impl TxSource for esplora_client::BlockingClient {
+    fn raw_tx(&self, txid: Txid) -> Result<Option<Transaction>> {
+        Ok(self.get_tx(&txid)?)
+    }
+}
+
+/// Moves a descriptor's keys from the receive to the change branch: each
+/// key's final `/0/*` step becomes `/1/*`. Fails on any other key.
+struct ChangeBranch;
+
+impl Translator<DescriptorPublicKey, DescriptorPublicKey, ()> for ChangeBranch {
+    fn pk(&mut self, key: &DescriptorPublicKey) -> std::result::Result<DescriptorPublicKey, ()> {
+        let DescriptorPublicKey::XPub(xkey) = key else {
+            return Err(());
+        };
+        let steps = xkey.derivation_path.as_ref();
+        if xkey.wildcard != Wildcard::Unhardened || steps.last() != Some(&ChildNumber::Normal { index: 0 }) {
+            return Err(());
+        }
+        let mut change_steps = steps.to_vec();
+        *change_steps.last_mut().unwrap() = ChildNumber::Normal { index: 1 };
+        Ok(DescriptorPublicKey::XPub(DescriptorXKey {
+            derivation_path: DerivationPath::from(change_steps),
+            ..xkey.clone()
+        }))
+    }
+
+    translate_hash_clone!(DescriptorPublicKey, DescriptorPublicKey, ());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::{extract_opreturn, parse_beacon_payload};
+    use std::sync::atomic::AtomicU32;
+    use bitcoin::{
+        absolute::LockTime, hashes::Hash, script::Instruction,
+        transaction::Version, OutPoint, Sequence, TxIn, TxOut, Witness,
+    };
+
+    fn test_xpub(seed_byte: u8) -> Xpub {
+        let secp = Secp256k1::new();
+        let xprv = Xpriv::new_master(Network::Testnet, &[seed_byte; 32]).unwrap();
+        Xpub::from_priv(&secp, &xprv)
+    }
+
+    /// Wallet with no file path, so tests never touch `~/.bitcoin-multisig`.
+    fn test_wallet() -> MultisigWallet {
+        let xpubs = vec![test_xpub(1), test_xpub(2), test_xpub(3)];
+        MultisigWallet::new_ephemeral(xpubs, 2, Network::Testnet).unwrap()
+    }
+
+    /// Drops the default `~/.bitcoin-multisig` path constructors give a
+    /// wallet, so the test neither reads that wallet's cache nor saves there.
+    fn detached(mut wallet: MultisigWallet) -> MultisigWallet {
+        wallet.set_path(PathBuf::new());
+        wallet
+    }
+
+    /// Gives the in-memory wallet an unconfirmed UTXO of `amount` sats.
+    fn fund_wallet(wallet: &mut Wallet, amount: u64) -> Txid {
+        fund_wallet_at(wallet, 0, amount, 0)
+    }
+
+    /// Like `fund_wallet`, paying external address `index` in a transaction
+    /// last seen at `last_seen`.
+    fn fund_wallet_at(wallet: &mut Wallet, index: u32, amount: u64, last_seen: u64) -> Txid {
+        let tx = funding_tx(wallet, index, amount);
+        let txid = tx.compute_txid();
+        wallet.apply_unconfirmed_txs([(tx, last_seen)]);
+        txid
+    }
+
+    /// Transaction paying `amount` to external address `index`.
+    fn funding_tx(wallet: &Wallet, index: u32, amount: u64) -> Transaction {
+        let address = wallet.peek_address(KeychainKind::External, index);
+        // Spend a distinct fake outpoint each time so fundings don't conflict,
+        // even across wallets whose changesets are later merged
+        static NEXT_VOUT: AtomicU32 = AtomicU32::new(0);
+        let vout = NEXT_VOUT.fetch_add(1, Ordering::Relaxed);
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::all_zeros(), vout),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(amount),
+                script_pubkey: address.script_pubkey(),
+            }],
+        }
+    }
+
+    struct MockChain {
+        hashes: BTreeMap<u32, BlockHash>,
+    }
+
+    impl BlockSource for MockChain {
+        fn block_hash(&self, height: u32) -> Result<Option<BlockHash>> {
+            Ok(self.hashes.get(&height).copied())
+        }
+    }
+
+    #[test]
+    fn test_opreturn_payload_embedded() {
+        let mut multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+
+        let payload = b"encrypted recovery hint";
+        let recipient = multisig.get_new_address().unwrap();
+        let psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient, payload, FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None).unwrap();
+
+        let op_return = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .find(|out| out.script_pubkey.is_op_return())
+            .expect("OP_RETURN output");
+        let pushed: Vec<u8> = op_return
+            .script_pubkey
+            .instructions()
+            .find_map(|ins| match ins {
+                Ok(Instruction::PushBytes(bytes)) => Some(bytes.as_bytes().to_vec()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(pushed, payload);
+    }
+
+    #[test]
+    fn test_opreturn_payload_too_large() {
+        let mut multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+
+        let recipient = multisig.get_new_address().unwrap();
+        let result = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient, &[0u8; 81], FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None);
+        let err = result.unwrap_err();
+        assert!(matches!(err, WalletError::OpReturnTooLarge { size: 81, max: 80 }));
+        assert!(err.to_string().contains("80-byte"));
+    }
+
+    #[test]
+    fn test_parse_beacon_payload_roundtrip() {
+        let mut multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+
+        let payload = [0xab; 80];
+        let recipient = multisig.get_new_address().unwrap();
+        let psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient, &payload, FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None).unwrap();
+
+        assert_eq!(parse_beacon_payload(&psbt.unsigned_tx), Some(payload.to_vec()));
+        assert_eq!(extract_opreturn(&psbt.unsigned_tx), Some(payload.to_vec()));
+    }
+
+    struct MockActivity {
+        funded: Address,
+    }
+
+    impl AddressActivity for MockActivity {
+        fn received_sats(&self, address: &Address) -> Result<(u64, u64)> {
+            Ok(if *address == self.funded { (7_000, 3_000) } else { (0, 0) })
+        }
+    }
+
+    #[test]
+    fn test_find_beacon_activity() {
+        let mut wallet = test_wallet();
+        let mut other = detached(MultisigWallet::new(vec![test_xpub(4), test_xpub(5)], 2, Network::Testnet).unwrap());
+        let mut third = detached(MultisigWallet::new(vec![test_xpub(6), test_xpub(7)], 2, Network::Testnet).unwrap());
+        let addrs = vec![
+            wallet.get_new_address().unwrap(),
+            other.get_new_address().unwrap(),
+            third.get_new_address().unwrap(),
+        ];
+
+        let source = MockActivity { funded: addrs[1].clone() };
+        let activity = MultisigWallet::collect_beacon_activity(&source, &addrs).unwrap();
+        assert_eq!(activity, vec![(addrs[1].clone(), 10_000)]);
+    }
+
+    #[test]
+    fn test_to_bdk_network_all_variants() {
+        let mut wallet = test_wallet();
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            wallet.network = network;
+            assert_eq!(wallet.to_bdk_network(), network);
+        }
+    }
+
+    #[test]
+    fn test_address_iter_matches_get_addresses() {
+        let wallet = test_wallet();
+        let from_iter: Vec<(u32, Address)> = wallet.address_iter(KeychainKind::External).unwrap().take(3).collect();
+        let addresses = wallet.get_addresses(0, 3).unwrap();
+
+        assert_eq!(from_iter.len(), 3);
+        for (i, (index, address)) in from_iter.into_iter().enumerate() {
+            assert_eq!(index, i as u32);
+            assert_eq!(address, addresses[i]);
+        }
+        assert_ne!(addresses[0], addresses[1]);
+    }
+
+    #[test]
+    fn test_descriptor_parsed_once_after_load() {
+        let wallet = MultisigWallet::from_json(&test_wallet().to_json().unwrap()).unwrap();
+        assert!(wallet.parsed_descriptor.get().is_none());
+
+        let first = wallet.fingerprint_address().unwrap();
+        assert!(wallet.parsed_descriptor.get().is_some());
+        for _ in 0..10 {
+            assert_eq!(wallet.fingerprint_address().unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn test_get_new_address_advances() {
+        let mut wallet = test_wallet();
+        let addresses = wallet.get_addresses(0, 3).unwrap();
+        for expected in &addresses {
+            assert_eq!(&wallet.get_new_address().unwrap(), expected);
+        }
+
+        assert_eq!(wallet.address_at(1).unwrap(), addresses[1]);
+
+        let change = wallet.get_change_address().unwrap();
+        assert_eq!(change, wallet.create_wallet().unwrap().peek_address(KeychainKind::Internal, 0).address);
+        assert!(!addresses.contains(&change));
+    }
+
+    #[test]
+    fn test_signing_progress() {
+        use bitcoin::{ecdsa, secp256k1::{Message, SecretKey}, PublicKey};
+
+        let mut multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_new_address().unwrap();
+        let mut psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient, b"beacon", FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None).unwrap();
+
+        let progress = multisig.signing_progress(&psbt).unwrap();
+        assert_eq!(progress, SigningProgress { collected: 0, required: 2, can_finalize: false });
+
+        let secp = Secp256k1::new();
+        let outsider = SecretKey::from_slice(&[9u8; 32]).unwrap();
+        // Progress looks at whose signatures they are, not whether they verify
+        let sig = ecdsa::Signature::sighash_all(secp.sign_ecdsa(&Message::from_digest([1u8; 32]), &outsider));
+        let add_sig = |psbt: &mut Psbt, cosigner: Option<usize>| {
+            for input in psbt.inputs.iter_mut() {
+                let key = match cosigner {
+                    Some(n) => PublicKey::new(*input.bip32_derivation.keys().nth(n).unwrap()),
+                    None => PublicKey::new(outsider.public_key(&secp)),
+                };
+                input.partial_sigs.insert(key, sig);
+            }
+        };
+
+        add_sig(&mut psbt, None);
+        let progress = multisig.signing_progress(&psbt).unwrap();
+        assert_eq!(progress, SigningProgress { collected: 0, required: 2, can_finalize: false });
+
+        add_sig(&mut psbt, Some(0));
+        let progress = multisig.signing_progress(&psbt).unwrap();
+        assert_eq!(progress, SigningProgress { collected: 1, required: 2, can_finalize: false });
+
+        add_sig(&mut psbt, Some(1));
+        let progress = multisig.signing_progress(&psbt).unwrap();
+        assert_eq!(progress, SigningProgress { collected: 2, required: 2, can_finalize: true });
+
+        add_sig(&mut psbt, Some(2));
+        let progress = multisig.signing_progress(&psbt).unwrap();
+        assert_eq!(progress, SigningProgress { collected: 2, required: 2, can_finalize: true });
+    }
+
+    #[test]
+    fn test_psbt_inputs_carry_cosigner_derivations() {
+        let fingerprints = ["d34db33f", "deadbeef", "f00dbabe"].map(|fp| Fingerprint::from_str(fp).unwrap());
+        let path = DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let keys = fingerprints
+            .iter()
+            .zip([1u8, 2, 3])
+            .map(|(fp, seed_byte)| (*fp, path.clone(), test_xpub(seed_byte)))
+            .collect();
+        let mut multisig = detached(MultisigWallet::new_with_origins(keys, 2, Network::Testnet).unwrap());
+        assert!(multisig.descriptor.contains("[d34db33f/48'/1'/0'/2']"));
+
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_new_address().unwrap();
+        let psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient, b"beacon", FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None).unwrap();
+
+        assert!(!psbt.inputs.is_empty());
+        for input in &psbt.inputs {
+            assert_eq!(input.bip32_derivation.len(), 3);
+            let mut seen: Vec<Fingerprint> = input.bip32_derivation.values().map(|(fp, _)| *fp).collect();
+            seen.sort();
+            assert_eq!(seen, fingerprints.to_vec());
+            for (_, origin_path) in input.bip32_derivation.values() {
+                assert!(origin_path.to_string().starts_with("48'/1'/0'/2'/0/"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ephemeral_wallet_does_not_persist() {
+        let xpubs = vec![test_xpub(1), test_xpub(2), test_xpub(3)];
+        let mut wallet = MultisigWallet::new_ephemeral(xpubs, 2, Network::Testnet).unwrap();
+
+        assert!(wallet.wallet_path.as_os_str().is_empty());
+        assert_eq!(wallet.get_new_address().unwrap(), test_wallet().get_new_address().unwrap());
+        assert!(matches!(wallet.save(), Err(WalletError::NoWalletPath)));
+
+        let path = std::env::temp_dir().join("bitcoin-multisig-ephemeral-test.json");
+        wallet.set_path(path.clone());
+        wallet.save().unwrap();
+        assert_eq!(MultisigWallet::load(path.clone()).unwrap().descriptor, wallet.descriptor);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_refuses_to_overwrite_different_wallet() {
+        let path = std::env::temp_dir().join("bitcoin-multisig-overwrite-test.json");
+        let _ = fs::remove_file(&path);
+        let mut first = test_wallet();
+        first.set_path(path.clone());
+        first.save().unwrap();
+        // Saving the same wallet again is fine
+        first.save().unwrap();
+
+        let mut second = detached(MultisigWallet::new(vec![test_xpub(4), test_xpub(5)], 2, Network::Testnet).unwrap());
+        second.set_path(path.clone());
+        match second.save() {
+            Err(WalletError::WalletConflict { path: conflict, existing }) => {
+                assert_eq!(conflict, path);
+                assert_eq!(existing, first.descriptor);
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        assert_eq!(MultisigWallet::load(path.clone()).unwrap().descriptor, first.descriptor);
+
+        second.save_with_overwrite(true).unwrap();
+        assert_eq!(MultisigWallet::load(path.clone()).unwrap().descriptor, second.descriptor);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_named_wallets_in_dir() {
+        let dir = std::env::temp_dir().join("bitcoin-multisig-named-wallets");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut first = test_wallet();
+        first.set_path(MultisigWallet::named_wallet_path_in(&dir, "test-named-a").unwrap());
+        let mut second = MultisigWallet::new_ephemeral(vec![test_xpub(4), test_xpub(5)], 1, Network::Testnet).unwrap();
+        second.set_path(MultisigWallet::named_wallet_path_in(&dir, "test-named-b").unwrap());
+        first.save().unwrap();
+        second.save().unwrap();
+        fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        assert_eq!(MultisigWallet::list_wallets_in(&dir).unwrap(), vec!["test-named-a", "test-named-b"]);
+        let loaded = MultisigWallet::load(MultisigWallet::named_wallet_path_in(&dir, "test-named-b").unwrap()).unwrap();
+        assert_eq!(loaded.descriptor, second.descriptor);
+        assert!(matches!(
+            MultisigWallet::named_wallet_path_in(&dir, "../wallet"),
+            Err(WalletError::InvalidWalletName(_))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_new_address_persists_across_loads() {
+        let path = std::env::temp_dir().join("bitcoin-multisig-address-index-test.json");
+        let _ = fs::remove_file(&path);
+        let mut wallet = test_wallet();
+        wallet.set_path(path.clone());
+        wallet.save().unwrap();
+
+        let first = wallet.get_new_address().unwrap();
+        let second = wallet.get_new_address().unwrap();
+        assert_ne!(first, second);
+
+        // A new process picks up where the last one stopped
+        let mut reloaded = MultisigWallet::load(path.clone()).unwrap();
+        let third = reloaded.get_new_address().unwrap();
+        assert_eq!(third, wallet.address_at(2).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().contains(r#""last_revealed_index": 2"#));
+
+        let fixed = detached(MultisigWallet::from_descriptor_str(&format!("wsh(pk({}/0/5))", test_xpub(1)), Network::Testnet).unwrap());
+        assert!(fixed.address_at(0).is_ok());
+        assert!(matches!(fixed.address_at(1), Err(WalletError::AddressIndexOutOfRange { index: 1, count: 1 })));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_wallet_round_trip() {
+        let path = std::env::temp_dir().join("bitcoin-multisig-encrypted-test.json");
+        let _ = fs::remove_file(&path);
+        let mut wallet = test_wallet();
+        wallet.set_path(path.clone());
+        wallet.save_encrypted("hunter2").unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap_or_default().contains("wsh("));
+        assert!(matches!(MultisigWallet::load(path.clone()), Err(WalletError::Encryption(_))));
+        assert!(matches!(
+            MultisigWallet::load_encrypted(path.clone(), "wrong"),
+            Err(WalletError::Encryption(_))
+        ));
+        let loaded = MultisigWallet::load_encrypted(path.clone(), "hunter2").unwrap();
+        assert_eq!(loaded.descriptor, wallet.descriptor);
+
+        // A plaintext save can't tell what the encrypted file holds
+        assert!(matches!(wallet.save(), Err(WalletError::WalletConflict { .. })));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_from_cosigner_files() {
+        let dir = std::env::temp_dir();
+        let coldcard = dir.join("bitcoin-multisig-coldcard-export.json");
+        let generic = dir.join("bitcoin-multisig-generic-export.json");
+        fs::write(
+            &coldcard,
+            format!(r#"{{"xfp": "D34DB33F", "p2wsh_deriv": "m/48'/1'/0'/2'", "p2wsh": "{}"}}"#, test_xpub(1)),
+        )
+        .unwrap();
+        fs::write(
+            &generic,
+            format!(r#"{{"xpub": "{}", "fingerprint": "deadbeef", "path": "m/48'/1'/0'/2'"}}"#, test_xpub(2)),
+        )
+        .unwrap();
+
+        let mut wallet = detached(MultisigWallet::from_cosigner_files(&[coldcard.clone(), generic.clone()], 2, Network::Testnet).unwrap());
+        assert!(wallet.descriptor.starts_with("wsh(multi(2,"));
+        assert!(wallet.descriptor.contains(&format!("[d34db33f/48'/1'/0'/2']{}/0/*", test_xpub(1))));
+        assert!(wallet.descriptor.contains(&format!("[deadbeef/48'/1'/0'/2']{}/0/*", test_xpub(2))));
+        assert!(wallet.get_new_address().is_ok());
+
+        fs::remove_file(coldcard).unwrap();
+        fs::remove_file(generic).unwrap();
+    }
+
+    #[test]
+    fn test_balance_of_address() {
+        let wallet = test_wallet();
+        let addrs = wallet.get_addresses(0, 2).unwrap();
+        let source = MockActivity { funded: addrs[0].clone() };
+
+        assert_eq!(wallet.address_received(&source, &addrs[0]).unwrap(), (7_000, 3_000));
+        assert_eq!(wallet.address_received(&source, &addrs[1]).unwrap(), (0, 0));
+
+        let foreign = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet)
+            .unwrap()
+            .get_new_address()
+            .unwrap();
+        assert!(matches!(wallet.address_received(&source, &foreign), Err(WalletError::NotMine(_))));
+    }
+
+    #[test]
+    fn test_get_transaction_by_txid() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        let txid = fund_wallet(&mut wallet, 50_000);
+
+        let summary = MultisigWallet::tx_summary(&wallet, txid, true).unwrap();
+        assert_eq!(summary.txid, txid);
+        assert_eq!(summary.received, 50_000);
+        assert_eq!(summary.sent, 0);
+        assert_eq!(summary.confirmation_height, None);
+        assert!(summary.raw_hex.is_some());
+        assert!(MultisigWallet::tx_summary(&wallet, txid, false).unwrap().raw_hex.is_none());
+
+        let random = Txid::from_byte_array([0x42; 32]);
+        assert!(MultisigWallet::tx_summary(&wallet, random, false).is_none());
+    }
+
+    #[test]
+    fn test_new_from_keys_plain_account_xpub() {
+        let keys = vec![test_xpub(1).to_string(), test_xpub(2).to_string()];
+        let wallet = detached(MultisigWallet::new_from_keys(&keys, 2, Network::Testnet).unwrap());
+        let expected = MultisigWallet::new_ephemeral(vec![test_xpub(1), test_xpub(2)], 2, Network::Testnet).unwrap();
+
+        assert_eq!(wallet.descriptor, expected.descriptor);
+        assert_eq!(wallet.get_addresses(0, 3).unwrap(), expected.get_addresses(0, 3).unwrap());
+    }
+
+    #[test]
+    fn test_new_from_keys_with_existing_path() {
+        let keys = vec![format!("{}/1/*", test_xpub(1)), test_xpub(2).to_string()];
+        let wallet = detached(MultisigWallet::new_from_keys(&keys, 2, Network::Testnet).unwrap());
+
+        assert!(wallet.descriptor.contains(&format!("{}/1/*", test_xpub(1))));
+        assert!(!wallet.descriptor.contains(&format!("{}/1/*/0/*", test_xpub(1))));
+        assert!(wallet.descriptor.contains(&format!("{}/0/*", test_xpub(2))));
+
+        let fixed = vec![format!("{}/1", test_xpub(1)), test_xpub(2).to_string()];
+        assert!(matches!(
+            MultisigWallet::new_from_keys(&fixed, 2, Network::Testnet),
+            Err(WalletError::InvalidXpub(_))
+        ));
+    }
+
+    #[test]
+    fn test_address_from_descriptor_matches_wallet() {
+        let wallet = test_wallet();
+        let addresses = wallet.get_addresses(0, 3).unwrap();
+        for (index, address) in addresses.iter().enumerate() {
+            assert_eq!(&address_from_descriptor(&wallet.descriptor, index as u32, Network::Testnet).unwrap(), address);
+        }
+
+        let multipath = format!("wsh(multi(2,{}/<0;1>/*,{}/<0;1>/*,{}/<0;1>/*))", test_xpub(1), test_xpub(2), test_xpub(3));
+        assert_eq!(address_from_descriptor(&multipath, 1, Network::Testnet).unwrap(), addresses[1]);
+    }
+
+    #[test]
+    fn test_full_scan_reports_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let wallet = test_wallet().create_wallet().unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let mut request = MultisigWallet::full_scan_request(&wallet, move |p| sink.lock().unwrap().push(p));
+
+        // Consume scripts the way a scanning backend would
+        let scanned = request.iter_spks(KeychainKind::External).take(5).count();
+        assert_eq!(scanned, 5);
+
+        let reports = reports.lock().unwrap();
+        assert!(!reports.is_empty());
+        assert_eq!(reports[0], ScanProgress { keychain: KeychainKind::External, index: 0, scripts_processed: 1 });
+        assert_eq!(reports.last().unwrap().scripts_processed, reports.len());
+    }
+
+    #[test]
+    fn test_from_descriptor_detects_script_type() {
+        let (x1, x2, x3) = (test_xpub(1), test_xpub(2), test_xpub(3));
+        let cases = [
+            (format!("wsh(multi(2,{}/0/*,{}/0/*,{}/0/*))", x1, x2, x3), ScriptType::P2wsh),
+            (format!("sh(wsh(multi(2,{}/0/*,{}/0/*,{}/0/*)))", x1, x2, x3), ScriptType::P2shP2wsh),
+            (format!("tr({}/0/*,multi_a(2,{}/0/*,{}/0/*))", x1, x2, x3), ScriptType::P2tr),
+        ];
+        for (descriptor, expected) in cases {
+            let wallet = detached(MultisigWallet::from_descriptor(&descriptor, Network::Testnet).unwrap());
+            assert_eq!(wallet.script_type().unwrap(), expected);
+        }
+
+        let descriptor = format!("wsh(multi(2,{}/0/*,{}/0/*,{}/0/*))", x1, x2, x3);
+        let wallet = detached(MultisigWallet::from_descriptor(&descriptor, Network::Testnet).unwrap());
+        let checksum = wallet.descriptor_checksum();
+        assert_eq!(checksum.len(), 8);
+        assert_eq!(wallet.export_descriptor().unwrap(), format!("{}#{}", descriptor, checksum));
+        assert!(MultisigWallet::from_descriptor(&format!("{}#{}", descriptor, checksum), Network::Testnet).is_ok());
+        let corrupted = if checksum.starts_with('q') { "p" } else { "q" };
+        assert!(matches!(
+            MultisigWallet::from_descriptor(&format!("{}#{}{}", descriptor, corrupted, &checksum[1..]), Network::Testnet),
+            Err(WalletError::Descriptor(_))
+        ));
+
+        let unsupported = format!("pkh({}/0/*)", x1);
+        assert!(matches!(
+            MultisigWallet::from_descriptor(&unsupported, Network::Testnet),
+            Err(WalletError::Descriptor(_))
+        ));
+    }
+
+    #[test]
+    fn test_nested_segwit_address_prefix() {
+        let secp = Secp256k1::new();
+        let mainnet_xpubs: Vec<Xpub> = [1u8, 2, 3]
+            .iter()
+            .map(|b| Xpub::from_priv(&secp, &Xpriv::new_master(Network::Bitcoin, &[*b; 32]).unwrap()))
+            .collect();
+        let mut mainnet = detached(MultisigWallet::new_with_script_type(mainnet_xpubs, 2, Network::Bitcoin, ScriptType::P2shP2wsh).unwrap());
+        assert!(mainnet.descriptor.starts_with("sh(wsh(multi(2,"));
+        assert_eq!(mainnet.script_type().unwrap(), ScriptType::P2shP2wsh);
+        assert!(mainnet.get_new_address().unwrap().to_string().starts_with('3'));
+
+        let testnet_xpubs = vec![test_xpub(1), test_xpub(2), test_xpub(3)];
+        let mut testnet = detached(MultisigWallet::new_with_script_type(testnet_xpubs, 2, Network::Testnet, ScriptType::P2shP2wsh).unwrap());
+        assert!(testnet.get_new_address().unwrap().to_string().starts_with('2'));
+        assert_eq!(testnet.threshold().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_max_sendable() {
+        let mut multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let to = multisig.get_new_address().unwrap();
+        let fee_rate = FeeRate::from_sat_per_vb(5).unwrap();
+
+        let max = MultisigWallet::max_sendable_in(&mut wallet, &to, fee_rate, &FeeLimits::default()).unwrap();
+        let fee = Amount::from_sat(100_000) - max;
+        // One 2-of-3 P2WSH input and one output is roughly 150 vbytes
+        assert!(fee >= fee_rate.fee_vb(100).unwrap());
+        assert!(fee <= fee_rate.fee_vb(250).unwrap());
+
+        let mut small = multisig.create_wallet().unwrap();
+        fund_wallet(&mut small, 600);
+        let high_rate = FeeRate::from_sat_per_vb(50).unwrap();
+        assert_eq!(MultisigWallet::max_sendable_in(&mut small, &to, high_rate, &FeeLimits::default()).unwrap(), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_detect_used_beyond_gap() {
+        let wallet = test_wallet();
+        let addrs = wallet.get_addresses(0, 71).unwrap();
+
+        let source = MockActivity { funded: addrs[70].clone() };
+        assert_eq!(wallet.highest_used_from(&source, STOP_GAP as u32).unwrap(), Some(70));
+
+        let within_gap = MockActivity { funded: addrs[3].clone() };
+        assert_eq!(wallet.highest_used_from(&within_gap, STOP_GAP as u32).unwrap(), None);
+    }
+
+    #[test]
+    fn test_create_transaction_abs_fee() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let to = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet)
+            .unwrap()
+            .get_new_address()
+            .unwrap();
+        let recipients = [(to, Amount::from_sat(50_000))];
+
+        let fee = Amount::from_sat(1_000);
+        let psbt = MultisigWallet::build_abs_fee_psbt(&mut wallet, &recipients, fee, ChangePolicy::Sequential, None, &FeeLimits::default()).unwrap();
+        let spent: Amount = psbt.inputs.iter().map(|input| input.witness_utxo.as_ref().unwrap().value).sum();
+        let output: Amount = psbt.unsigned_tx.output.iter().map(|out| out.value).sum();
+        assert_eq!(spent - output, fee);
+
+        let too_low = MultisigWallet::build_abs_fee_psbt(&mut wallet, &recipients, Amount::from_sat(10), ChangePolicy::Sequential, None, &FeeLimits::default());
+        assert!(matches!(too_low, Err(WalletError::InvalidFee(_))));
+        let too_high = MultisigWallet::build_abs_fee_psbt(&mut wallet, &recipients, Amount::from_sat(50_000), ChangePolicy::Sequential, None, &FeeLimits::default());
+        assert!(matches!(too_high, Err(WalletError::InvalidFee(_))));
+    }
+
+    #[test]
+    fn test_hardened_suffix_rejected() {
+        let xpubs = vec![test_xpub(1), test_xpub(2)];
+        let result = MultisigWallet::descriptor_from_xpubs(xpubs.clone(), 2, ScriptType::P2wsh, "/0'/*");
+        match result {
+            Err(WalletError::Descriptor(msg)) => assert!(msg.contains("hardened")),
+            other => panic!("expected hardened suffix error, got {:?}", other),
+        }
+        assert!(MultisigWallet::descriptor_from_xpubs(xpubs.clone(), 2, ScriptType::P2wsh, "/0/*'").is_err());
+        assert!(MultisigWallet::descriptor_from_xpubs(xpubs, 2, ScriptType::P2wsh, "/1/*").is_ok());
+    }
+
+    #[test]
+    fn test_transactions_paged() {
+        let mut wallet = test_wallet().create_wallet().unwrap();
+        for i in 1..=5 {
+            fund_wallet_at(&mut wallet, 0, i * 1_000, i);
+        }
+
+        let all = MultisigWallet::transactions_page(&wallet, 0, usize::MAX);
+        let received: Vec<u64> = all.iter().map(|tx| tx.received).collect();
+        assert_eq!(received, vec![5_000, 4_000, 3_000, 2_000, 1_000]);
+
+        let page = MultisigWallet::transactions_page(&wallet, 2, 2);
+        assert_eq!(page, all[2..4].to_vec());
+        assert!(MultisigWallet::transactions_page(&wallet, 5, 2).is_empty());
+    }
+
+    struct MockFees(HashMap<u16, f64>);
+
+    impl FeeSource for MockFees {
+        fn fee_estimates(&self) -> Result<HashMap<u16, f64>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_fee_rate_from_estimates() {
+        let limits = FeeLimits::default();
+        let fees = MockFees(HashMap::from([(1, 20.5), (3, 12.0), (6, 8.0), (144, 0.5)]));
+        assert_eq!(MultisigWallet::fee_rate_from(&fees, 6, &limits).unwrap(), FeeRate::from_sat_per_vb_unchecked(8));
+        // No estimate for 5 blocks; the 3 block one is the nearest faster target
+        assert_eq!(MultisigWallet::fee_rate_from(&fees, 5, &limits).unwrap(), FeeRate::from_sat_per_vb_unchecked(12));
+        assert_eq!(MultisigWallet::fee_rate_from(&fees, 1, &limits).unwrap(), FeeRate::from_sat_per_kwu(5125));
+        // Below the floor
+        assert_eq!(MultisigWallet::fee_rate_from(&fees, 200, &limits).unwrap(), FeeRate::BROADCAST_MIN);
+
+        let floor = FeeLimits { min_fee_rate: FeeRate::from_sat_per_vb_unchecked(3), ..FeeLimits::default() };
+        let empty = MockFees(HashMap::new());
+        assert_eq!(MultisigWallet::fee_rate_from(&empty, 6, &floor).unwrap(), FeeRate::from_sat_per_vb_unchecked(3));
+
+        let spike = MockFees(HashMap::from([(1, 5_000.0)]));
+        assert!(matches!(MultisigWallet::fee_rate_from(&spike, 1, &limits), Err(WalletError::InvalidFee(_))));
+    }
+
+    #[test]
+    fn test_manual_coin_selection() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        let small = fund_wallet_at(&mut wallet, 0, 10_000, 0);
+        let medium = fund_wallet_at(&mut wallet, 1, 30_000, 0);
+        fund_wallet_at(&mut wallet, 2, 80_000, 0);
+        let recipient = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet)
+            .unwrap()
+            .get_new_address()
+            .unwrap();
+
+        let chosen = vec![OutPoint::new(small, 0), OutPoint::new(medium, 0)];
+        let psbt = MultisigWallet::build_manual_psbt(
+            &mut wallet,
+            &[(recipient.clone(), 35_000)],
+            &chosen,
+            FeeRate::BROADCAST_MIN,
+            ChangePolicy::Sequential,
+            None,
+        )
+        .unwrap();
+        let mut spent: Vec<OutPoint> = psbt.unsigned_tx.input.iter().map(|input| input.previous_output).collect();
+        spent.sort();
+        let mut expected = chosen.clone();
+        expected.sort();
+        assert_eq!(spent, expected);
+
+        // 40k selected can't pay 39.9k plus the fee
+        let result = MultisigWallet::build_manual_psbt(
+            &mut wallet,
+            &[(recipient.clone(), 39_900)],
+            &chosen,
+            FeeRate::BROADCAST_MIN,
+            ChangePolicy::Sequential,
+            None,
+        );
+        match result {
+            Err(WalletError::InsufficientFunds { needed, available }) => {
+                assert_eq!(available, Amount::from_sat(40_000));
+                assert!(needed > Amount::from_sat(39_900));
+            }
+            other => panic!("expected insufficient funds, got {:?}", other),
+        }
+
+        let result = MultisigWallet::build_manual_psbt(
+            &mut wallet,
+            &[(recipient.clone(), 50_000)],
+            &chosen,
+            FeeRate::BROADCAST_MIN,
+            ChangePolicy::Sequential,
+            None,
+        );
+        match result {
+            Err(WalletError::InsufficientFunds { needed, available }) => {
+                assert_eq!(needed, Amount::from_sat(50_000));
+                assert_eq!(available, Amount::from_sat(40_000));
+            }
+            other => panic!("expected insufficient funds, got {:?}", other),
+        }
+
+        let unknown = vec![OutPoint::new(Txid::all_zeros(), 7)];
+        assert!(matches!(
+            MultisigWallet::build_manual_psbt(&mut wallet, &[(recipient, 1_000)], &unknown, FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None),
+            Err(WalletError::Transaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_random_change_policy_stays_within_gap() {
+        let multisig = test_wallet();
+        let recipient = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet)
+            .unwrap()
+            .get_new_address()
+            .unwrap();
+
+        for _ in 0..10 {
+            let mut wallet = multisig.create_wallet().unwrap();
+            fund_wallet(&mut wallet, 100_000);
+            let psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, recipient.clone(), b"beacon", FeeRate::BROADCAST_MIN, ChangePolicy::Random, None)
+                .unwrap();
+
+            let change = psbt
+                .unsigned_tx
+                .output
+                .iter()
+                .find(|out| wallet.is_mine(out.script_pubkey.clone()))
+                .expect("change output");
+            let (_, index) = wallet.derivation_of_spk(change.script_pubkey.clone()).unwrap();
+            assert!(index < STOP_GAP as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_single_sig_taproot() {
+        let mut wallet = detached(MultisigWallet::single_sig_taproot(test_xpub(1), Network::Testnet).unwrap());
+        assert!(wallet.descriptor.starts_with("tr("));
+        assert_eq!(wallet.script_type().unwrap(), ScriptType::P2tr);
+
+        let address = wallet.get_new_address().unwrap();
+        assert!(address.to_string().starts_with("tb1p"));
+
+        let mut wsh = MultisigWallet::new_ephemeral(vec![test_xpub(1)], 1, Network::Testnet).unwrap();
+        assert_ne!(address, wsh.get_new_address().unwrap());
+    }
+
+    #[test]
+    fn test_fee_limits_reject_and_override() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let to = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet)
+            .unwrap()
+            .get_new_address()
+            .unwrap();
+        let strict = FeeLimits::default();
+        let lenient = FeeLimits { allow_high_fee: true, ..FeeLimits::default() };
+
+        let typo_rate = FeeRate::from_sat_per_vb(5_000).unwrap();
+        let result = MultisigWallet::max_sendable_in(&mut wallet, &to, typo_rate, &strict);
+        assert!(matches!(result, Err(WalletError::InvalidFee(_))));
+        assert!(MultisigWallet::max_sendable_in(&mut wallet, &to, typo_rate, &lenient).is_ok());
+
+        let recipients = [(to, Amount::from_sat(50_000))];
+        let high_fee = Amount::from_sat(20_000);
+        let result = MultisigWallet::build_abs_fee_psbt(&mut wallet, &recipients, high_fee, ChangePolicy::Sequential, None, &strict);
+        assert!(matches!(result, Err(WalletError::InvalidFee(_))));
+        assert!(MultisigWallet::build_abs_fee_psbt(&mut wallet, &recipients, high_fee, ChangePolicy::Sequential, None, &lenient).is_ok());
+    }
+
+    #[test]
+    fn test_used_addresses() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet_at(&mut wallet, 0, 10_000, 0);
+        fund_wallet_at(&mut wallet, 2, 25_000, 0);
+
+        let addresses = multisig.get_addresses(0, 3).unwrap();
+        let used = MultisigWallet::used_addresses_in(&wallet);
+        assert_eq!(used, vec![(0, addresses[0].clone(), 10_000), (2, addresses[2].clone(), 25_000)]);
+    }
+
+    #[test]
+    fn test_list_utxos() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        let first = fund_wallet_at(&mut wallet, 0, 10_000, 0);
+        let second = fund_wallet_at(&mut wallet, 3, 25_000, 0);
+
+        let mut utxos = MultisigWallet::list_utxos_in(&wallet);
+        utxos.sort_by_key(|utxo| utxo.derivation_index);
+        assert_eq!(
+            utxos,
+            vec![
+                Utxo {
+                    outpoint: OutPoint::new(first, 0),
+                    value_sats: 10_000,
+                    keychain: KeychainKind::External,
+                    derivation_index: 0,
+                    confirmation_height: None,
+                },
+                Utxo {
+                    outpoint: OutPoint::new(second, 0),
+                    value_sats: 25_000,
+                    keychain: KeychainKind::External,
+                    derivation_index: 3,
+                    confirmation_height: None,
+                },
+            ]
+        );
+        let json = serde_json::to_value(&utxos[0]).unwrap();
+        assert_eq!(json["value_sats"], 10_000);
+    }
+
+    #[test]
+    fn test_keychain_report() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet_at(&mut wallet, 0, 10_000, 0);
+        fund_wallet_at(&mut wallet, 2, 25_000, 0);
+
+        let report = MultisigWallet::keychain_report_in(&wallet, KeychainKind::External);
+        assert_eq!(report.len(), 3 + STOP_GAP);
+        assert_eq!(report[2].address, multisig.get_addresses(2, 1).unwrap()[0]);
+        assert!(report[0].used && report[2].used);
+        assert_eq!(report[0].received_sats, 10_000);
+        assert_eq!(report[2].received_sats, 25_000);
+        assert!(!report[1].used);
+        assert_eq!(report[1].received_sats, 0);
+        assert!(report[1].revealed);
+        assert!(report[3..].iter().all(|status| !status.revealed && !status.used && status.received_sats == 0));
+    }
+
+    #[test]
+    fn test_status_of_funded_wallet() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 40_000);
+
+        let status = multisig.status_of(&wallet).unwrap();
+        assert_eq!(status.policy, "2-of-3");
+        assert_eq!(status.unconfirmed_sats, 40_000);
+        assert_eq!(status.utxo_count, 1);
+        assert_eq!(status.tx_count, 1);
+        assert!(status.to_string().contains("40000 sats"));
+    }
+
+    #[test]
+    fn test_sync_emits_debug_log() {
+        use std::sync::Mutex;
+
+        struct CaptureLogger(Mutex<Vec<String>>);
+
+        impl log::Log for CaptureLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push(format!("{} {}", record.level(), record.args()));
+            }
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        // Regtest has no default backend without `esplora_url`, so the sync
+        // stops before any request
+        let mut wallet = test_wallet();
+        wallet.network = Network::Regtest;
+        assert!(wallet.sync_wallet().is_err());
+        assert!(wallet.sync_wallet_quick().is_err());
+
+        let lines = LOGGER.0.lock().unwrap();
+        assert!(lines.iter().any(|line| line == "DEBUG Starting sync of regtest wallet"));
+        assert!(lines.iter().any(|line| *line == format!("DEBUG Using stop gap {}", STOP_GAP)));
+        assert!(lines.iter().any(|line| *line == format!("DEBUG Using stop gap {}", QUICK_STOP_GAP)));
+    }
+
+    #[test]
+    fn test_full_sync_ignores_cached_changeset() {
+        let mut multisig = test_wallet();
+        let path = std::env::temp_dir().join("bitcoin-multisig-refresh-test.json");
+        multisig.set_path(path.clone());
+
+        let mut cached = multisig.create_wallet().unwrap();
+        fund_wallet(&mut cached, 30_000);
+        multisig.store_changeset(&cached.take_staged().unwrap()).unwrap();
+
+        let chain = MockChain { hashes: BTreeMap::new() };
+        let (from_cache, _) = multisig.starting_wallet(false, &chain).unwrap();
+        assert_eq!(from_cache.balance().total(), Amount::from_sat(30_000));
+
+        let (refreshed, changeset) = multisig.starting_wallet(true, &chain).unwrap();
+        assert_eq!(refreshed.balance().total(), Amount::ZERO);
+        assert!(changeset.tx_graph.txs.is_empty());
+
+        fs::remove_file(multisig.changeset_path().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_reorg_drops_orphaned_transaction() {
+        use bdk_wallet::{chain::{BlockId, ConfirmationBlockTime, TxUpdate}, Update};
+        use std::sync::Arc;
+
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        let orphaned = BlockId { height: 100, hash: BlockHash::from_byte_array([0xaa; 32]) };
+
+        let tx = funding_tx(&wallet, 0, 20_000);
+        let txid = tx.compute_txid();
+        let mut tx_update = TxUpdate::default();
+        tx_update.anchors.insert((ConfirmationBlockTime { block_id: orphaned, confirmation_time: 0 }, txid));
+        tx_update.txs.push(Arc::new(tx));
+        let update = Update {
+            tx_update,
+            chain: Some(wallet.latest_checkpoint().insert(orphaned)),
+            ..Update::default()
+        };
+        wallet.apply_update(update).unwrap();
+        assert_eq!(wallet.balance().confirmed, Amount::from_sat(20_000));
+
+        let mut changeset = wallet.take_staged().unwrap();
+        let chain = MockChain { hashes: BTreeMap::from([(100, BlockHash::from_byte_array([0xbb; 32]))]) };
+        MultisigWallet::drop_reorged_blocks(&mut changeset, &chain).unwrap();
+        assert!(!changeset.local_chain.blocks.contains_key(&100));
+        assert!(changeset.tx_graph.anchors.is_empty());
+        assert!(changeset.tx_graph.txs.iter().all(|tx| tx.compute_txid() != txid));
+
+        let reloaded = multisig.wallet_from_changeset(changeset).unwrap().unwrap();
+        assert_eq!(reloaded.balance().total(), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_reorg_treats_unknown_height_as_stale() {
+        use bdk_wallet::chain::BlockId;
+
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        let kept = BlockId { height: 50, hash: BlockHash::from_byte_array([0xcc; 32]) };
+        let tip = BlockId { height: 100, hash: BlockHash::from_byte_array([0xaa; 32]) };
+        let checkpoint = wallet.latest_checkpoint().insert(kept).insert(tip);
+        wallet.apply_update(bdk_wallet::Update { chain: Some(checkpoint), ..Default::default() }).unwrap();
+        let mut changeset = wallet.take_staged().unwrap();
+
+        // The backend's chain ends below the cached tip; the block at 50
+        // still matches, so nothing under it is touched
+        let chain = MockChain { hashes: BTreeMap::from([(50, kept.hash)]) };
+        MultisigWallet::drop_reorged_blocks(&mut changeset, &chain).unwrap();
+        assert!(!changeset.local_chain.blocks.contains_key(&100));
+        assert_eq!(changeset.local_chain.blocks.get(&50), Some(&Some(kept.hash)));
+        assert!(changeset.local_chain.blocks.contains_key(&0));
+    }
+
+    #[test]
+    fn test_abs_fee_rejects_dust_recipient() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let to = multisig.get_addresses(1, 1).unwrap().remove(0);
+
+        let recipients = [(to, Amount::from_sat(100))];
+        let result = MultisigWallet::build_abs_fee_psbt(
+            &mut wallet,
+            &recipients,
+            Amount::from_sat(50),
+            ChangePolicy::Sequential,
+            None,
+            &FeeLimits::default(),
+        );
+        assert!(matches!(result, Err(WalletError::BelowDust { limit, .. }) if limit == Amount::from_sat(330)));
+    }
+
+    #[test]
+    fn test_receive_uris() {
+        let mut multisig = test_wallet();
+        let wallet = multisig.create_wallet().unwrap();
+        let addresses = multisig.get_addresses(0, 3).unwrap();
+
+        let uris = multisig
+            .receive_uris_in(&wallet, 3, Some(Amount::from_sat(150_000)), Some("Coffee & cake"))
+            .unwrap();
+        assert_eq!(uris.len(), 3);
+        for (uri, address) in uris.iter().zip(&addresses) {
+            assert!(uri.starts_with("bitcoin:tb1"));
+            assert_eq!(uri, &format!("bitcoin:{}?amount=0.0015&label=Coffee%20%26%20cake", address));
+        }
+
+        let bare = multisig.receive_uris_in(&wallet, 1, None, None).unwrap();
+        assert_eq!(bare, vec![format!("bitcoin:{}", addresses[0])]);
+
+        // Addresses already handed out by get_new_address are skipped
+        assert_eq!(multisig.get_new_address().unwrap(), addresses[0]);
+        let after = multisig.receive_uris_in(&wallet, 1, None, None).unwrap();
+        assert_eq!(after, vec![format!("bitcoin:{}", addresses[1])]);
+    }
+
+    #[test]
+    fn test_keys_match_descriptor() {
+        let wallet = test_wallet();
+        let xprivs: Vec<Xpriv> = [1u8, 2, 9]
+            .iter()
+            .map(|b| Xpriv::new_master(Network::Testnet, &[*b; 32]).unwrap())
+            .collect();
+        assert_eq!(wallet.keys_match_descriptor(&xprivs).unwrap(), vec![true, true, false]);
+
+        let secp = Secp256k1::new();
+        let master = Xpriv::new_master(Network::Testnet, &[4u8; 32]).unwrap();
+        let path = DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let account = Xpub::from_priv(&secp, &master.derive_priv(&secp, &path).unwrap());
+        let keys = vec![(master.fingerprint(&secp), path, account), (Fingerprint::default(), DerivationPath::master(), test_xpub(5))];
+        let with_origins = detached(MultisigWallet::new_with_origins(keys, 1, Network::Testnet).unwrap());
+        assert_eq!(with_origins.keys_match_descriptor(&[master, xprivs[2]]).unwrap(), vec![true, false]);
+    }
+
+    #[test]
+    fn test_merge_changeset_unions_transactions() {
+        let mut multisig = test_wallet();
+        let path = std::env::temp_dir().join("bitcoin-multisig-merge-test.json");
+        multisig.set_path(path);
+
+        let mut first = multisig.create_wallet().unwrap();
+        let first_txid = fund_wallet(&mut first, 10_000);
+        multisig.store_changeset(&first.take_staged().unwrap()).unwrap();
+
+        let mut second = multisig.create_wallet().unwrap();
+        let second_txid = fund_wallet_at(&mut second, 1, 20_000, 5);
+        multisig.merge_changeset(&second.take_staged().unwrap()).unwrap();
+
+        let merged = multisig.wallet_from_changeset(multisig.load_changeset().unwrap().unwrap()).unwrap().unwrap();
+        assert!(merged.get_tx(first_txid).is_some());
+        assert!(merged.get_tx(second_txid).is_some());
+
+        let other = MultisigWallet::new_ephemeral(vec![test_xpub(8), test_xpub(9)], 1, Network::Testnet).unwrap();
+        let foreign = other.create_wallet().unwrap().take_staged().unwrap();
+        assert!(matches!(multisig.merge_changeset(&foreign), Err(WalletError::Cache(_))));
+
+        fs::remove_file(multisig.changeset_path().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_sync_cancelled() {
+        let wallet = test_wallet();
+        let cancel = AtomicBool::new(true);
+
+        let start = std::time::Instant::now();
+        assert!(matches!(wallet.sync_wallet_cancellable(&cancel), Err(WalletError::Cancelled)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_derive_script_pubkeys() {
+        let wallet = test_wallet();
+        let scripts = wallet.derive_script_pubkeys(KeychainKind::External, 10..110).unwrap();
+        assert_eq!(scripts.len(), 100);
+        assert!(scripts.iter().all(|script| script.is_p2wsh()));
+
+        let addresses = wallet.get_addresses(10, 2).unwrap();
+        assert_eq!(scripts[0], addresses[0].script_pubkey());
+        assert_eq!(scripts[1], addresses[1].script_pubkey());
+    }
+
+    #[test]
+    fn test_balance_cross_check() {
+        let backends = vec![
+            Backend::Esplora("https://esplora-a.example/api/".to_string()),
+            Backend::Esplora("https://esplora-b.example/api/".to_string()),
+        ];
+
+        let agree = MultisigWallet::cross_check_with(&backends, |_| Ok(50_000)).unwrap();
+        assert_eq!(agree, vec![(backends[0].clone(), 50_000), (backends[1].clone(), 50_000)]);
+
+        let disagree = MultisigWallet::cross_check_with(&backends, |backend| {
+            Ok(if *backend == backends[0] { 50_000 } else { 20_000 })
+        })
+        .unwrap();
+        assert_ne!(disagree[0].1, disagree[1].1);
+        assert_eq!(disagree[1], (backends[1].clone(), 20_000));
+    }
+
+    #[test]
+    fn test_reused_addresses() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet_at(&mut wallet, 0, 10_000, 1);
+        fund_wallet_at(&mut wallet, 0, 15_000, 2);
+        fund_wallet_at(&mut wallet, 1, 5_000, 3);
+
+        let reused = MultisigWallet::reused_addresses_in(&wallet);
+        assert_eq!(reused, vec![(multisig.get_addresses(0, 1).unwrap().remove(0), 2)]);
+    }
+
+    #[test]
+    fn test_fingerprint_address_matches_across_cosigners() {
+        let mut xpubs = vec![test_xpub(3), test_xpub(1), test_xpub(2)];
+        xpubs.sort_by_key(|xpub| xpub.to_string());
+        let ours = detached(MultisigWallet::new(xpubs.clone(), 2, Network::Testnet).unwrap());
+        let theirs = MultisigWallet::from_descriptor(
+            &MultisigWallet::new(xpubs, 2, Network::Testnet).unwrap().export_descriptor().unwrap(),
+            Network::Testnet,
+        )
+        .unwrap();
+
+        let address = ours.fingerprint_address().unwrap();
+        assert_eq!(address, theirs.fingerprint_address().unwrap());
+        assert_eq!(address, ours.get_addresses(0, 1).unwrap()[0]);
+        assert!(ours.setup_checklist().unwrap().contains(&format!("Verification address: {}", address)));
+        assert!(ours.setup_checklist().unwrap().contains("Policy: 2-of-3"));
+    }
+
+    #[test]
+    fn test_default_backend_per_network() {
+        let mut wallet = test_wallet();
+        wallet.network = Network::Signet;
+        assert_eq!(
+            wallet.default_backend().unwrap(),
+            Backend::Esplora("https://mempool.space/signet/api/".to_string())
+        );
+
+        wallet.network = Network::Regtest;
+        assert!(matches!(wallet.default_backend(), Err(WalletError::UnsupportedNetwork(_))));
+        wallet.esplora_url = Some("http://127.0.0.1:3002".to_string());
+        assert_eq!(
+            wallet.default_backend().unwrap(),
+            Backend::Esplora("http://127.0.0.1:3002".to_string())
+        );
+    }
+
+    #[test]
+    fn test_to_watch_only_has_no_private_keys() {
+        let mut wallet = test_wallet();
+        let mut watch_only = wallet.to_watch_only().unwrap();
+
+        assert!(!watch_only.descriptor.contains("xprv"));
+        assert!(!watch_only.descriptor.contains("tprv"));
+        assert_eq!(watch_only.descriptor, wallet.descriptor);
+        assert!(watch_only.wallet_path.as_os_str().is_empty());
+        assert_eq!(watch_only.get_new_address().unwrap(), wallet.get_new_address().unwrap());
+    }
+
+    #[test]
+    fn test_replace_key() {
+        let mut wallet = test_wallet();
+        let mut replaced = wallet.replace_key(&test_xpub(2), test_xpub(4)).unwrap();
+
+        assert_ne!(replaced.descriptor, wallet.descriptor);
+        assert!(replaced.descriptor.contains(&test_xpub(4).to_string()));
+        assert!(!replaced.descriptor.contains(&test_xpub(2).to_string()));
+        assert!(Descriptor::<DescriptorPublicKey>::from_str(&replaced.descriptor).is_ok());
+        assert_eq!(replaced.policy().unwrap(), "2-of-3");
+        assert_ne!(replaced.get_new_address().unwrap(), wallet.get_new_address().unwrap());
+
+        assert!(matches!(
+            wallet.replace_key(&test_xpub(5), test_xpub(4)),
+            Err(WalletError::InvalidXpub(_))
+        ));
+    }
+    #[test]
+    fn test_spendable_utxos_skip_immature_coinbase() {
+        use bdk_wallet::{chain::{BlockId, ConfirmationBlockTime, TxUpdate}, Update};
+        use std::sync::Arc;
+
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 10_000);
+
+        let mut coinbase = funding_tx(&wallet, 1, 50_000);
+        coinbase.input[0].previous_output = OutPoint::null();
+        let mined = BlockId { height: 100, hash: BlockHash::from_byte_array([0xcc; 32]) };
+        let mut tx_update = TxUpdate::default();
+        tx_update.anchors.insert((ConfirmationBlockTime { block_id: mined, confirmation_time: 0 }, coinbase.compute_txid()));
+        tx_update.txs.push(Arc::new(coinbase));
+        let update = Update {
+            tx_update,
+            chain: Some(wallet.latest_checkpoint().insert(mined)),
+            ..Update::default()
+        };
+        wallet.apply_update(update).unwrap();
+
+        let spendable = |height| {
+            let mut values: Vec<u64> = multisig
+                .spendable_utxos_in(&wallet, height)
+                .unwrap()
+                .iter()
+                .map(|utxo| utxo.txout.value.to_sat())
+                .collect();
+            values.sort();
+            values
+        };
+        assert_eq!(spendable(150), vec![10_000]);
+        assert_eq!(spendable(198), vec![10_000]);
+        assert_eq!(spendable(199), vec![10_000, 50_000]);
+    }
+
+    #[test]
+    fn test_build_psbt_with_two_recipients() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipients = multisig.get_addresses(10, 2).unwrap();
+
+        let psbt = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, None, |tx_builder| {
+            tx_builder.add_recipient(recipients[0].script_pubkey(), Amount::from_sat(20_000));
+            tx_builder.add_recipient(recipients[1].script_pubkey(), Amount::from_sat(30_000));
+            tx_builder.fee_rate(FeeRate::from_sat_per_vb_unchecked(2));
+        })
+        .unwrap();
+
+        for (address, amount) in recipients.iter().zip([20_000, 30_000]) {
+            assert!(psbt
+                .unsigned_tx
+                .output
+                .iter()
+                .any(|out| out.script_pubkey == address.script_pubkey() && out.value.to_sat() == amount));
+        }
+        assert_eq!(psbt.unsigned_tx.output.len(), 3);
+    }
+
+    #[test]
+    fn test_check_key_depths() {
+        let secp = Secp256k1::new();
+        let master = Xpriv::new_master(Network::Testnet, &[1; 32]).unwrap();
+        let path = DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let account = Xpub::from_priv(&secp, &master.derive_priv(&secp, &path).unwrap());
+        assert!(MultisigWallet::check_key_depths(&[account]).is_ok());
+
+        let keys = [account, test_xpub(2)];
+        match MultisigWallet::check_key_depths(&keys) {
+            Err(WalletError::UnexpectedKeyDepth { xpub, depth }) => {
+                assert_eq!(xpub, test_xpub(2).to_string());
+                assert_eq!(depth, 0);
+            }
+            other => panic!("expected UnexpectedKeyDepth, got {:?}", other),
+        }
+
+        // Master keys only warn, and the unchecked constructor builds the same wallet
+        let warned = detached(MultisigWallet::new(keys.to_vec(), 2, Network::Testnet).unwrap());
+        let unchecked = detached(MultisigWallet::new_unchecked_depth(keys.to_vec(), 2, Network::Testnet).unwrap());
+        assert_eq!(warned.descriptor, unchecked.descriptor);
+    }
+
+    #[test]
+    fn test_fixed_descriptor_has_single_address() {
+        let desc = format!("wsh(multi(2,{},{}))", test_xpub(1), test_xpub(2));
+        let wallet = detached(MultisigWallet::from_descriptor(&desc, Network::Testnet).unwrap());
+
+        let addresses = wallet.get_addresses(0, 5).unwrap();
+        assert_eq!(addresses.len(), 1);
+        assert_eq!(addresses[0], address_from_descriptor(&desc, 0, Network::Testnet).unwrap());
+        assert_eq!(addresses[0], wallet.fingerprint_address().unwrap());
+        assert_eq!(wallet.derive_script_pubkeys(KeychainKind::External, 0..5).unwrap().len(), 1);
+        assert!(address_from_descriptor(&desc, 1, Network::Testnet).is_err());
+
+        let hardened = format!("wsh(multi(2,{}/0/*',{}/0/*))", test_xpub(1), test_xpub(2));
+        assert!(matches!(
+            MultisigWallet::from_descriptor(&hardened, Network::Testnet),
+            Err(WalletError::Descriptor(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimated_witness_weight() {
+        let two_of_three = test_wallet();
+        assert_eq!(two_of_three.estimated_witness_weight().unwrap(), 256);
+
+        let xpubs = (1..=5).map(test_xpub).collect();
+        let three_of_five = detached(MultisigWallet::new(xpubs, 3, Network::Testnet).unwrap());
+        assert_eq!(three_of_five.estimated_witness_weight().unwrap(), 398);
+
+        // Never below what miniscript reports as the maximum satisfaction
+        for wallet in [&two_of_three, &three_of_five] {
+            let max = wallet.parsed_descriptor().unwrap().max_weight_to_satisfy().unwrap();
+            assert!(wallet.estimated_witness_weight().unwrap() as u64 >= max.to_wu());
+        }
+    }
+
+    #[test]
+    fn test_sign_psbt_with_mnemonics() {
+        let secp = Secp256k1::new();
+        let path = DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let phrases: Vec<String> = [[21u8; 32], [22u8; 32]]
+            .iter()
+            .map(|entropy| Mnemonic::from_entropy(entropy).unwrap().to_string())
+            .collect();
+        let keys = phrases
+            .iter()
+            .map(|phrase| {
+                let seed = Mnemonic::parse(phrase).unwrap().to_seed("");
+                let master = Xpriv::new_master(Network::Testnet, &seed).unwrap();
+                let account = Xpub::from_priv(&secp, &master.derive_priv(&secp, &path).unwrap());
+                (master.fingerprint(&secp), path.clone(), account)
+            })
+            .collect();
+        let multisig = detached(MultisigWallet::new_with_origins(keys, 2, Network::Testnet).unwrap());
+
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_addresses(5, 1).unwrap().remove(0);
+        let mut psbt = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, None, |tx_builder| {
+            tx_builder.add_recipient(recipient.script_pubkey(), Amount::from_sat(40_000));
+        })
+        .unwrap();
+
+        assert!(multisig.sign_psbt_with_mnemonic(&mut psbt, &phrases[0], None, &path).unwrap());
+        assert_eq!(multisig.signing_progress(&psbt).unwrap().collected, 1);
+        assert!(multisig.sign_psbt_with_mnemonic(&mut psbt, &phrases[1], None, &path).unwrap());
+        assert!(multisig.signing_progress(&psbt).unwrap().can_finalize);
+
+        let stranger = Mnemonic::from_entropy(&[23u8; 32]).unwrap().to_string();
+        assert!(matches!(
+            multisig.sign_psbt_with_mnemonic(&mut psbt, &stranger, None, &path),
+            Err(WalletError::Key(_))
+        ));
+        assert!(matches!(
+            multisig.sign_psbt_with_mnemonic(&mut psbt, &phrases[0], Some("wrong"), &path),
+            Err(WalletError::Key(_))
+        ));
+    }
+
+    #[test]
+    fn test_offline_sign_and_finalize() {
+        let mut multisig = test_wallet();
+        // Any network access would fail against this backend
+        multisig.esplora_url = Some("http://127.0.0.1:1".to_string());
+
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_addresses(5, 1).unwrap().remove(0);
+        let unsigned = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, None, |tx_builder| {
+            tx_builder.add_recipient(recipient.script_pubkey(), Amount::from_sat(40_000));
+        })
+        .unwrap();
+
+        // Round-trip through the binary file format used by air-gapped signers
+        let mut psbt = Psbt::deserialize(&unsigned.serialize()).unwrap();
+        let xpriv = |seed_byte| Xpriv::new_master(Network::Testnet, &[seed_byte; 32]).unwrap();
+        assert!(matches!(multisig.sign_psbt(&mut psbt, &xpriv(9)), Err(WalletError::Key(_))));
+        assert!(!multisig.finalize_psbt(&mut psbt).unwrap());
+
+        assert!(multisig.sign_psbt(&mut psbt, &xpriv(1)).unwrap());
+        assert!(multisig.sign_psbt(&mut psbt, &xpriv(3)).unwrap());
+        assert!(multisig.finalize_psbt(&mut psbt).unwrap());
+
+        let tx = psbt.extract_tx().unwrap();
+        assert!(tx.input.iter().all(|input| !input.witness.is_empty()));
+    }
+
+    #[test]
+    fn test_core_import_descriptors() {
+        let wallet = test_wallet();
+        let json: serde_json::Value = serde_json::from_str(&wallet.core_import_descriptors("now").unwrap()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let external = entries[0]["desc"].as_str().unwrap();
+        let internal = entries[1]["desc"].as_str().unwrap();
+        assert_eq!(external, wallet.export_descriptor().unwrap());
+        assert!(internal.contains("/1/*") && !internal.contains("/0/*"));
+        assert_eq!(internal, with_checksum(internal).unwrap());
+        assert!(Descriptor::<DescriptorPublicKey>::from_str(internal).is_ok());
+        for (entry, internal) in entries.iter().zip([false, true]) {
+            assert_eq!(entry["internal"], internal);
+            assert_eq!(entry["active"], true);
+            assert_eq!(entry["timestamp"], "now");
+            assert_eq!(entry["range"], serde_json::json!([0, CORE_IMPORT_RANGE_END]));
+        }
+
+        let dated: serde_json::Value = serde_json::from_str(&wallet.core_import_descriptors("1700000000").unwrap()).unwrap();
+        assert_eq!(dated[0]["timestamp"], 1_700_000_000u64);
+        assert!(wallet.core_import_descriptors("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_export_bsms() {
+        let wallet = test_wallet();
+        let record = wallet.export_bsms().unwrap();
+        let lines: Vec<&str> = record.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "BSMS 1.0");
+        let template = lines[1].split('#').next().unwrap();
+        assert_eq!(template.replace("/**", "/0/*"), wallet.descriptor.split('#').next().unwrap());
+        assert_eq!(lines[1], with_checksum(lines[1]).unwrap());
+        assert_eq!(lines[2], "/0/*,/1/*");
+        assert_eq!(lines[3], wallet.fingerprint_address().unwrap().to_string());
+    }
+
+    #[test]
+    fn test_wallet_fingerprint() {
+        let ordered = test_wallet();
+        let shuffled = detached(MultisigWallet::new(vec![test_xpub(3), test_xpub(1), test_xpub(2)], 2, Network::Testnet).unwrap());
+        assert_ne!(ordered.descriptor, shuffled.descriptor);
+
+        let id = ordered.wallet_fingerprint().unwrap();
+        assert_eq!(id.len(), 8);
+        assert_eq!(id, shuffled.wallet_fingerprint().unwrap());
+
+        let three_of_three = detached(MultisigWallet::new(vec![test_xpub(1), test_xpub(2), test_xpub(3)], 3, Network::Testnet).unwrap());
+        assert_ne!(id, three_of_three.wallet_fingerprint().unwrap());
+    }
+
+    #[test]
+    fn test_verify_transaction_flags_burns_and_nonstandard_outputs() {
+        use bitcoin::opcodes::OP_TRUE;
+
+        let multisig = test_wallet();
+        let wallet = multisig.create_wallet().unwrap();
+        let mut tx = funding_tx(&wallet, 0, 50_000);
+        assert!(multisig.verify_transaction(&tx).unwrap().is_clean());
+
+        let data = PushBytesBuf::try_from(b"beacon".to_vec()).unwrap();
+        tx.output.push(TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new_op_return(&data) });
+        tx.output.push(TxOut { value: Amount::from_sat(1_000), script_pubkey: ScriptBuf::new_op_return(&data) });
+        tx.output.push(TxOut {
+            value: Amount::from_sat(2_000),
+            script_pubkey: ScriptBuf::builder().push_opcode(OP_TRUE).into_script(),
+        });
+
+        let verification = multisig.verify_transaction(&tx).unwrap();
+        assert_eq!(verification.burned_outputs, vec![(2, Amount::from_sat(1_000))]);
+        assert_eq!(verification.nonstandard_outputs, vec![3]);
+        assert!(!verification.is_clean());
+    }
+
+    #[test]
+    fn test_fetch_raw_tx() {
+        use bitcoin::consensus::encode::deserialize_hex;
+
+        struct MockTxSource(BTreeMap<Txid, String>);
+
+        impl TxSource for MockTxSource {
+            fn raw_tx(&self, txid: Txid) -> Result<Option<Transaction>> {
+                Ok(self.0.get(&txid).map(|hex| deserialize_hex(hex).unwrap()))
+            }
+        }
+
+        let wallet = test_wallet().create_wallet().unwrap();
+        let tx = funding_tx(&wallet, 0, 25_000);
+        let txid = tx.compute_txid();
+        let source = MockTxSource(BTreeMap::from([(txid, serialize_hex(&tx))]));
+
+        let fetched = MultisigWallet::fetch_raw_tx_from(&source, txid).unwrap();
+        assert_eq!(fetched.compute_txid(), txid);
+        assert_eq!(fetched, tx);
+
+        let unknown = Txid::from_byte_array([7; 32]);
+        assert!(matches!(
+            MultisigWallet::fetch_raw_tx_from(&source, unknown),
+            Err(WalletError::TxNotFound(missing)) if missing == unknown
+        ));
+    }
+
+    #[test]
+    fn test_build_psbt_locktime() {
+        let multisig = test_wallet();
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_addresses(5, 1).unwrap().remove(0);
+        let pay = |tx_builder: &mut TxBuilder<'_, DefaultCoinSelectionAlgorithm>| {
+            tx_builder.add_recipient(recipient.script_pubkey(), Amount::from_sat(40_000));
+        };
+
+        let requested = absolute::LockTime::from_height(800_000).unwrap();
+        let psbt = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, Some(requested), pay).unwrap();
+        assert_eq!(psbt.unsigned_tx.lock_time, requested);
+
+        let tip = wallet.latest_checkpoint().height();
+        let psbt = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, None, pay).unwrap();
+        assert_eq!(psbt.unsigned_tx.lock_time, absolute::LockTime::from_height(tip).unwrap());
+    }
+
+    #[test]
+    fn test_can_sign() {
+        let wallet = test_wallet();
+        assert!(!wallet.can_sign());
+
+        let xpriv = Xpriv::new_master(Network::Testnet, &[1; 32]).unwrap();
+        let mut signing = test_wallet();
+        signing.descriptor = format!("wsh(multi(2,{}/0/*,{}/0/*))", xpriv, test_xpub(2));
+        assert!(signing.can_sign());
+    }
+
+    #[test]
+    fn test_tx_id_of_finalized_psbt() {
+        let multisig = detached(MultisigWallet::new(vec![test_xpub(1), test_xpub(2)], 2, Network::Testnet).unwrap());
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let recipient = multisig.get_addresses(5, 1).unwrap().remove(0);
+        let mut psbt = MultisigWallet::build_psbt_in(&mut wallet, ChangePolicy::Sequential, None, |tx_builder| {
+            tx_builder.add_recipient(recipient.script_pubkey(), Amount::from_sat(40_000));
+        })
+        .unwrap();
+        assert!(matches!(MultisigWallet::tx_id_of(&psbt), Err(WalletError::Transaction(_))));
+
+        for seed_byte in [1, 2] {
+            let xpriv = Xpriv::new_master(Network::Testnet, &[seed_byte; 32]).unwrap();
+            assert!(multisig.sign_psbt(&mut psbt, &xpriv).unwrap());
+        }
+        assert!(multisig.finalize_psbt(&mut psbt).unwrap());
+
+        let txid = MultisigWallet::tx_id_of(&psbt).unwrap();
+        assert_eq!(txid, psbt.extract_tx().unwrap().compute_txid());
+    }
+
+    #[test]
+    fn test_cache_discarded_when_descriptor_changes() {
+        let path = std::env::temp_dir().join("bitcoin-multisig-descriptor-change-test.json");
+        let mut original = test_wallet();
+        original.set_path(path.clone());
+        let mut cached = original.create_wallet().unwrap();
+        fund_wallet(&mut cached, 30_000);
+        original.store_changeset(&cached.take_staged().unwrap()).unwrap();
+
+        let chain = MockChain { hashes: BTreeMap::new() };
+        let (from_cache, _) = original.starting_wallet(false, &chain).unwrap();
+        assert_eq!(from_cache.balance().total(), Amount::from_sat(30_000));
+
+        // Same wallet file, edited to a different descriptor
+        let mut edited = detached(MultisigWallet::new(vec![test_xpub(1), test_xpub(2), test_xpub(4)], 2, Network::Testnet).unwrap());
+        edited.set_path(path.clone());
+        let (rebuilt, changeset) = edited.starting_wallet(false, &chain).unwrap();
+        assert_eq!(rebuilt.balance().total(), Amount::ZERO);
+        assert!(changeset.tx_graph.txs.is_empty());
+
+        // Storing the rebuilt state retags the cache for the new descriptor
+        edited.store_changeset(&changeset).unwrap();
+        assert!(edited.load_changeset().unwrap().is_some());
+        assert!(original.load_changeset().unwrap().is_none());
+
+        fs::remove_file(edited.changeset_path().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_separate_change_keychain() {
+        let multisig = test_wallet();
+        let wallet = multisig.create_wallet().unwrap();
+        let receive = wallet.peek_address(KeychainKind::External, 0).address;
+        let change = wallet.peek_address(KeychainKind::Internal, 0).address;
+        assert_ne!(receive, change);
+        let internal = multisig.internal_descriptor().unwrap().unwrap();
+        assert_eq!(change, address_from_descriptor(&internal, 0, Network::Testnet).unwrap());
+
+        // Keys are moved one by one, with or without an origin
+        let mixed = detached(MultisigWallet::new_from_keys(
+            &[format!("[d34db33f/48'/1'/0'/2']{}/0/*", test_xpub(1)), format!("{}/7/0/*", test_xpub(2))],
+            2,
+            Network::Testnet,
+        ).unwrap());
+        let internal = mixed.internal_descriptor().unwrap().unwrap();
+        assert!(internal.contains(&format!("[d34db33f/48'/1'/0'/2']{}/1/*", test_xpub(1))));
+        assert!(internal.contains(&format!("{}/7/1/*", test_xpub(2))));
+        assert!(Descriptor::<DescriptorPublicKey>::from_str(&internal).is_ok());
+
+        // Change from a spend lands on the internal keychain
+        let mut wallet = multisig.create_wallet().unwrap();
+        fund_wallet(&mut wallet, 100_000);
+        let psbt = MultisigWallet::build_opreturn_psbt(&mut wallet, receive, b"beacon", FeeRate::BROADCAST_MIN, ChangePolicy::Sequential, None).unwrap();
+        let change_out = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .find(|out| out.script_pubkey == change.script_pubkey())
+            .expect("change output");
+        assert_eq!(wallet.derivation_of_spk(change_out.script_pubkey.clone()), Some((KeychainKind::Internal, 0)));
+
+        // Without a `/0/*` path there is no internal keychain
+        let flat = detached(MultisigWallet::new_from_keys(&[format!("{}/*", test_xpub(1))], 1, Network::Testnet).unwrap());
+        let flat_wallet = flat.create_wallet().unwrap();
+        assert_eq!(
+            flat_wallet.peek_address(KeychainKind::Internal, 0).address,
+            flat_wallet.peek_address(KeychainKind::External, 0).address
+        );
+    }
+
+    #[test]
+    fn test_new_dual_keychains() {
+        let secp = Secp256k1::new();
+        let branch_xpubs = |branch: u32| -> Vec<Xpub> {
+            (1..=3u8)
+                .map(|seed_byte| {
+                    let master = Xpriv::new_master(Network::Testnet, &[seed_byte; 32]).unwrap();
+                    let path = DerivationPath::from_str(&format!("m/48'/1'/0'/2'/{}", branch)).unwrap();
+                    Xpub::from_priv(&secp, &master.derive_priv(&secp, &path).unwrap())
+                })
+                .collect()
+        };
+
+        let wallet = detached(MultisigWallet::new_dual(branch_xpubs(0), branch_xpubs(1), 2, Network::Testnet).unwrap());
+        let change_descriptor = wallet.change_descriptor.clone().unwrap();
+        let bdk_wallet = wallet.create_wallet().unwrap();
+        let receive = bdk_wallet.peek_address(KeychainKind::External, 0).address;
+        let change = bdk_wallet.peek_address(KeychainKind::Internal, 0).address;
+        assert_ne!(receive, change);
+        assert_eq!(receive, address_from_descriptor(&wallet.descriptor, 0, Network::Testnet).unwrap());
+        assert_eq!(change, address_from_descriptor(&change_descriptor, 0, Network::Testnet).unwrap());
+
+        // The change descriptor survives a save/load round trip
+        let loaded = MultisigWallet::from_json(&wallet.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.change_descriptor, Some(change_descriptor));
+
+        assert!(MultisigWallet::new_dual(branch_xpubs(0), branch_xpubs(1)[..2].to_vec(), 2, Network::Testnet).is_err());
+        let mut swapped = branch_xpubs(1);
+        swapped.swap(0, 1);
+        assert!(matches!(
+            MultisigWallet::new_dual(branch_xpubs(0), swapped, 2, Network::Testnet),
+            Err(WalletError::InvalidXpub(_))
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::error::{Result, WalletError};
+use crate::utilities::coin_type;
+use bdk_wallet::descriptor::{Descriptor, DescriptorPublicKey};
+use bitcoin::{bip32::{Fingerprint, Xpub}, Network};
+use std::str::FromStr;
+
+/// BIP48 script type for native segwit multisig (P2WSH)
+const BIP48_P2WSH_SCRIPT_TYPE: u32 = 2;
+
+/// Builds a `wsh(sortedmulti(...))` descriptor for cosigner xpubs derived at
+/// the standard BIP48 path `m/48'/coin'/0'/2'`.
+/// 
+/// # Arguments
+/// * `keys` - Master fingerprint and account xpub of each cosigner
+/// * `threshold` - Number of required signatures
+/// * `network` - Network, used to select the coin type
+/// 
+/// # Returns
+/// * The descriptor with key origins, `/<0;1>/*` suffixes and checksum
+pub fn bip48_p2wsh(keys: &[(Fingerprint, Xpub)], threshold: usize, network: Network) -> Result<String> {
+    if threshold == 0 {
+        return Err(WalletError::Descriptor("threshold must be at least 1".to_string()));
+    }
+    if threshold > keys.len() {
+        return Err(WalletError::ThresholdTooHigh { threshold, keys: keys.len() });
+    }
+
+    let origin = format!("48'/{}'/0'/{}'", coin_type(network)?, BIP48_P2WSH_SCRIPT_TYPE);
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|(fingerprint, xpub)| format!("[{}/{}]{}/<0;1>/*", fingerprint, origin, xpub))
+        .collect();
+
+    let desc_str = format!("wsh(sortedmulti({},{}))", threshold, keys.join(","));
+    let desc = Descriptor::<DescriptorPublicKey>::from_str(&desc_str)?;
+    Ok(desc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{bip32::{DerivationPath, Xpriv}, secp256k1::Secp256k1};
+
+    /// Master fingerprint and m/48'/1'/0'/2' account xpub for a test seed
+    fn test_account(seed_byte: u8) -> (Fingerprint, Xpub) {
+        let secp = Secp256k1::new();
+        let master = Xpriv::new_master(Network::Testnet, &[seed_byte; 32]).unwrap();
+        let path = DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let account = master.derive_priv(&secp, &path).unwrap();
+        (master.fingerprint(&secp), Xpub::from_priv(&secp, &account))
+    }
+
+    fn test_keys() -> Vec<(Fingerprint, Xpub)> {
+        (1..=3).map(test_account).collect()
+    }
+
+    #[test]
+    fn test_bip48_p2wsh_2_of_3() {
+        let descriptor = bip48_p2wsh(&test_keys(), 2, Network::Testnet).unwrap();
+        assert_eq!(
+            descriptor,
+            "wsh(sortedmulti(2,\
+             [4ba43603/48'/1'/0'/2']tpubDDwf2gdFxFahr9RUtDQCuZmsx34CfdZ7RALAirwC2FGeLBzW1TDiEpqFeRdxLdZD7rfsbZHYwSaT6CLM3TAcYRw6xfRv4U6KCQt4Zuhvjkz/<0;1>/*,\
+             [8dfc9b34/48'/1'/0'/2']tpubDEXiq2SVhhqALktxfVFgj3C9M3T2G7xL11iezYg2LJAf245YkNyqp2K9TrvHABDCp2232k34UegU4aKEtUZNigit8EEqoLNe2JKMzMiLwYq/<0;1>/*,\
+             [56c4fac3/48'/1'/0'/2']tpubDEg3kqr2jo5ergkJbFqRHvCpiob7wR7Hi44J7y987G1JZfbzBND77XKTyPZzGvh3uyDf8kexMJnFD9W8FuraJ4wLMsx6YuZVXRSRRcx6QdD/<0;1>/*\
+             ))#8lya595x"
+        );
+    }
+
+    #[test]
+    fn test_bip48_p2wsh_mainnet_coin_type() {
+        let descriptor = bip48_p2wsh(&test_keys(), 2, Network::Bitcoin).unwrap();
+        assert!(descriptor.contains("[4ba43603/48'/0'/0'/2']"));
+    }
+
+    #[test]
+    fn test_bip48_p2wsh_invalid_threshold() {
+        assert!(matches!(
+            bip48_p2wsh(&test_keys(), 4, Network::Testnet),
+            Err(WalletError::ThresholdTooHigh { threshold: 4, keys: 3 })
+        ));
+        assert!(matches!(
+            bip48_p2wsh(&test_keys(), 0, Network::Testnet),
+            Err(WalletError::Descriptor(_))
+        ));
+    }
+}
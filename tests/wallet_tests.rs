@@ -1,21 +1,23 @@
 mod tests {
-    use bitcoin::{bip32::ExtendedPubKey, secp256k1::{rand::{self, RngCore}, Secp256k1}, Network};
-    use bitcoin_multisig_wallet::MultisigWallet;
+    use bitcoin::{bip32::{Xpriv, Xpub}, secp256k1::{rand::{self, RngCore}, Secp256k1}, Network};
+    use bitcoin_multisig_wallet::{utilities::read_keys_file, BeaconSet, MultisigWallet, WalletError};
 
-    fn generate_random_xpub() -> ExtendedPubKey {
+    fn generate_random_xpub() -> Xpub {
         let secp = Secp256k1::new();
         let mut seed = [0u8; 32];
         rand::thread_rng().fill_bytes(&mut seed);
-        let xprv = bitcoin::bip32::ExtendedPrivKey::new_master(Network::Testnet, &seed).unwrap();
-        ExtendedPubKey::from_priv(&secp, &xprv)
+        let xprv = Xpriv::new_master(Network::Testnet, &seed).unwrap();
+        Xpub::from_priv(&secp, &xprv)
     }
 
     #[test]
     fn test_multisig_wallet_lifecycle() {
         let xpubs = vec![generate_random_xpub(), generate_random_xpub(), generate_random_xpub()];
-        let wallet = MultisigWallet::new(xpubs.clone(), 2, Network::Testnet).unwrap();
+        let mut wallet = MultisigWallet::new(xpubs.clone(), 2, Network::Testnet).unwrap();
+        // Random keys would conflict with a wallet left at the default path
+        wallet.set_path(std::env::temp_dir().join("bitcoin-multisig-lifecycle-test.json"));
 
-        wallet.save().unwrap();
+        wallet.save_with_overwrite(true).unwrap();
         let loaded = MultisigWallet::load(wallet.wallet_path.clone()).unwrap();
         assert_eq!(wallet.descriptor, loaded.descriptor);
 
@@ -24,5 +26,130 @@ mod tests {
 
         let balance = wallet.get_balance().unwrap();
         assert_eq!(balance, 0);
+        std::fs::remove_file(&wallet.wallet_path).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_load_legacy_wallet_json() {
+        let xpubs = vec![generate_random_xpub(), generate_random_xpub()];
+        let wallet = MultisigWallet::new(xpubs, 2, Network::Testnet).unwrap();
+
+        let legacy = format!(r#"{{"descriptor":"{}","network":"testnet"}}"#, wallet.descriptor);
+        let upgraded = MultisigWallet::from_json(&legacy).unwrap();
+        assert_eq!(upgraded.descriptor, wallet.descriptor);
+        assert_eq!(upgraded.network, Network::Testnet);
+
+        let json = upgraded.to_json().unwrap();
+        assert!(json.contains(r#""version": 1"#));
+        assert_eq!(MultisigWallet::from_json(&json).unwrap().descriptor, wallet.descriptor);
+
+        let corrupted = format!(r#"{{"descriptor":"{}é","network":"testnet"}}"#, wallet.descriptor);
+        assert!(matches!(MultisigWallet::from_json(&corrupted), Err(WalletError::Descriptor(_))));
+    }
+
+    #[test]
+    fn test_threshold_too_high_error() {
+        let xpubs = vec![generate_random_xpub(), generate_random_xpub()];
+        let result = MultisigWallet::new(xpubs, 3, Network::Testnet);
+        assert!(matches!(result, Err(WalletError::ThresholdTooHigh { threshold: 3, keys: 2 })));
+    }
+
+    #[test]
+    fn test_named_wallets() {
+        // Saving and listing are covered against a temp dir in the unit tests;
+        // nothing here writes to the wallet directory
+        let wallet = MultisigWallet::new_named(vec![generate_random_xpub(), generate_random_xpub()], 2, Network::Testnet, "test-named-a").unwrap();
+        assert!(wallet.wallet_path.ends_with("wallet-test-named-a.json"));
+        assert!(matches!(MultisigWallet::load_named("../wallet"), Err(WalletError::InvalidWalletName(_))));
+        assert!(matches!(
+            MultisigWallet::new_named(vec![generate_random_xpub()], 1, Network::Testnet, "a b"),
+            Err(WalletError::InvalidWalletName(_))
+        ));
+    }
+
+    #[test]
+    fn test_wallet_status_summary() {
+        let xpubs = vec![generate_random_xpub(), generate_random_xpub(), generate_random_xpub()];
+        let wallet = MultisigWallet::new_ephemeral(xpubs, 2, Network::Testnet).unwrap();
+
+        // status() would sync against Esplora; an unsynced wallet keeps this offline
+        let status = wallet.status_of(&wallet.create_wallet().unwrap()).unwrap();
+        assert_eq!(status.policy, "2-of-3");
+        assert_eq!(status.confirmed_sats, 0);
+
+        let summary = status.to_string();
+        assert!(summary.contains("2-of-3"));
+        assert!(summary.contains("0 sats"));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["policy"], "2-of-3");
+    }
+
+    #[test]
+    fn test_export_import_descriptor() {
+        let xpubs = vec![generate_random_xpub(), generate_random_xpub(), generate_random_xpub()];
+        let original = MultisigWallet::new_ephemeral(xpubs, 2, Network::Testnet).unwrap();
+        let exported = original.export_descriptor().unwrap();
+        assert!(exported.contains('#'));
+
+        let dir = std::env::temp_dir().join("bitcoin-multisig-import-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut imported = MultisigWallet::from_descriptor(&exported, Network::Testnet).unwrap();
+        imported.set_path(dir.join("wallet.json"));
+        imported.save().unwrap();
+
+        let reloaded = MultisigWallet::load(dir.join("wallet.json")).unwrap();
+        assert_eq!(reloaded.address_at(0).unwrap(), original.address_at(0).unwrap());
+        assert!(matches!(
+            MultisigWallet::from_descriptor(&exported, Network::Bitcoin),
+            Err(WalletError::NetworkMismatch { expected: Network::Bitcoin })
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_wallet_from_xpubs_file() {
+        let xpubs: Vec<String> = (0..3).map(|_| generate_random_xpub().to_string()).collect();
+        let dir = std::env::temp_dir();
+        let lines_file = dir.join("bitcoin-multisig-xpubs.txt");
+        let json_file = dir.join("bitcoin-multisig-xpubs.json");
+        std::fs::write(&lines_file, format!("# cosigners\n{}\n\n{}\n{}\n{}\n", xpubs[0], xpubs[1], xpubs[2], xpubs[0])).unwrap();
+        std::fs::write(&json_file, serde_json::to_string(&xpubs).unwrap()).unwrap();
+
+        let from_lines = read_keys_file(&lines_file).unwrap();
+        assert_eq!(from_lines, xpubs);
+        assert_eq!(read_keys_file(&json_file).unwrap(), xpubs);
+
+        let wallet = MultisigWallet::new_from_keys(&from_lines, 2, Network::Testnet).unwrap();
+        for xpub in &xpubs {
+            assert!(wallet.descriptor.contains(xpub.as_str()));
+        }
+
+        std::fs::remove_file(lines_file).unwrap();
+        std::fs::remove_file(json_file).unwrap();
+    }
+
+    #[test]
+    fn test_beacons_from_keys_manifest() {
+        let secp = Secp256k1::new();
+        let manifest: Vec<serde_json::Value> = (0..4)
+            .map(|_| {
+                let mut seed = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut seed);
+                let secret = bitcoin::secp256k1::SecretKey::from_slice(&seed).unwrap();
+                let public = bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &secret);
+                serde_json::json!({ "xpub": generate_random_xpub().to_string(), "publicKey": public.to_string() })
+            })
+            .collect();
+        let path = std::env::temp_dir().join("bitcoin-multisig-beacon-manifest.json");
+        std::fs::write(&path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
+
+        let beacon_set = BeaconSet::from_manifest(&path, Network::Testnet).unwrap();
+        assert_eq!(beacon_set.beacons.len(), 6);
+        let pairs: Vec<(usize, usize)> = beacon_set.beacons.iter().map(|beacon| beacon.pair).collect();
+        assert_eq!(pairs, vec![(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)]);
+        assert!(beacon_set.beacons.iter().all(|beacon| beacon.address.starts_with("tb1q")));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}